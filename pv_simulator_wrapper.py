@@ -40,13 +40,60 @@ def main():
         help="simulation output file in JSON format",
         metavar="OUT"
     )
+    parser.add_argument(
+        "-p",
+        "--peak-scaling",
+        dest="peak_scaling",
+        type=float,
+        default=1650.0,
+        help="scaling of the simulated power output, which peaks at roughly twice the scaling in W",
+        metavar="SCALING"
+    )
+    parser.add_argument(
+        "-c",
+        "--consumption-bound",
+        dest="consumption_bound",
+        type=float,
+        default=9000.0,
+        help="upper bound of the simulated power consumption in W",
+        metavar="BOUND"
+    )
+    parser.add_argument(
+        "--dawn",
+        dest="dawn",
+        type=float,
+        default=5.0,
+        help="start of the power output in h from midnight",
+        metavar="DAWN"
+    )
+    parser.add_argument(
+        "--dusk",
+        dest="dusk",
+        type=float,
+        default=21.0,
+        help="end of the power output in h from midnight",
+        metavar="DUSK"
+    )
+    parser.add_argument(
+        "--peak-output",
+        dest="peak_output",
+        type=float,
+        default=None,
+        help="peak power output in W, which takes precedence over the scaling",
+        metavar="PEAK"
+    )
     args = parser.parse_args()
-    # No error handling required as the underlying rust library does this.
+    # Invalid parameters raise a ValueError and failed simulations a RuntimeError.
     pv_simulator.simulate_pv_and_write_results_to_file(
         args.stride,
         args.length,
         args.broker,
-        args.output
+        args.output,
+        args.peak_scaling,
+        args.consumption_bound,
+        args.dawn,
+        args.dusk,
+        args.peak_output
     )
 
 
@@ -0,0 +1,94 @@
+//! The `compression` module allows compression of message bodies sent via the broker.
+extern crate flate2;
+
+use super::pv_error::PvError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
+
+/// The `Compression` algorithms available for message bodies.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Compression {
+    /// Compression in the gzip format.
+    Gzip,
+}
+
+impl Compression {
+    /// Returns the compression matching the specified content encoding property
+    /// of a broker message, if any.
+    ///
+    /// # Parameters
+    ///
+    /// * `content_encoding` - the content encoding of the message
+    pub fn from_content_encoding(content_encoding: &str) -> Option<Self> {
+        match content_encoding {
+            "gzip" => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+
+    /// Returns the content encoding property of broker messages compressed with this algorithm.
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+        }
+    }
+
+    /// Compresses the specified data.
+    /// Fails if the compression fails.
+    ///
+    /// # Parameters
+    ///
+    /// * `data` - the data to compress
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, PvError> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            },
+        }
+    }
+
+    /// Decompresses the specified data.
+    /// Fails if the data was not compressed with this algorithm.
+    ///
+    /// # Parameters
+    ///
+    /// * `data` - the data to decompress
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, PvError> {
+        match self {
+            Compression::Gzip => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Tests if compressed data is restored by decompression.
+    fn test_compress_decompress() {
+        let data = b"{\"power_consumption\":1000.0,\"time_stamp\":null}".repeat(10);
+        let compressed = Compression::Gzip.compress(&data).unwrap();
+        assert_ne!(compressed, data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(Compression::Gzip.decompress(&compressed).unwrap(), data);
+        assert!(Compression::Gzip.decompress(&data).is_err());
+    }
+
+    #[test]
+    /// Tests if the content encoding property is mapped to the correct compression.
+    fn test_content_encoding() {
+        assert_eq!(
+            Compression::from_content_encoding(Compression::Gzip.content_encoding()),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::from_content_encoding("identity"), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! The `http` module allows triggering simulations via HTTP, so the simulation can be
+//! run as a web service.
+extern crate tiny_http;
+
+use super::meter::Meter;
+use super::photovoltaic_simulator::PvSimulator;
+use super::pv_error::PvError;
+use super::seed_sequence::SeedSequence;
+use super::simulate_pv_in_memory;
+use super::simulated_time::{duration_from_secs, SimulatedDateTime};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::net::ToSocketAddrs;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+/// The default exclusive upper bound of the simulated power consumption in watt.
+const DEFAULT_CONSUMPTION_BOUND: f64 = 9000.0;
+/// The maximum number of simulated time points per request, which bounds the memory and
+/// time a single request can occupy the server for.
+const MAX_TIME_POINTS: f64 = 1_000_000.0;
+
+/// The `SimulationParameters` expected as JSON body of a simulation request.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct SimulationParameters {
+    /// The simulated time steps in seconds.
+    pub stride_in_sec: f64,
+    /// The total simulation length in hours.
+    pub simulation_length_in_h: f64,
+    /// The exclusive upper bound of the simulated power consumption in watt.
+    #[serde(default = "default_consumption_bound")]
+    pub consumption_bound: f64,
+    /// The master seed for a reproducible simulation, if any.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Returns the default exclusive upper bound of the simulated power consumption in watt.
+fn default_consumption_bound() -> f64 {
+    DEFAULT_CONSUMPTION_BOUND
+}
+
+impl SimulationParameters {
+    /// Returns the stride and the length of the simulation.
+    /// Fails if either is not a positive finite number or if the simulation exceeds
+    /// the maximum number of time points.
+    fn simulation_time(&self) -> Result<(Duration, Duration), PvError> {
+        let stride = duration_from_secs(self.stride_in_sec)?;
+        let simulation_length = duration_from_secs(self.simulation_length_in_h * 3600.0)?;
+        if stride <= Duration::zero() {
+            return Err(PvError::InternalError(format!(
+                "{} is not a positive stride.",
+                self.stride_in_sec
+            )));
+        }
+        let time_points = self.simulation_length_in_h * 3600.0 / self.stride_in_sec;
+        if time_points > MAX_TIME_POINTS {
+            return Err(PvError::InternalError(format!(
+                "{} time points exceed the maximum of {}.",
+                time_points.ceil(),
+                MAX_TIME_POINTS
+            )));
+        }
+        Ok((stride, simulation_length))
+    }
+}
+
+/// Serves simulations at the specified address.
+/// Every POST request with `SimulationParameters` as JSON body is answered with
+/// the simulated records as JSON. Requests that cannot be answered are reported to
+/// standard error without stopping the server.
+/// Fails if the address cannot be bound.
+///
+/// # Parameters
+///
+/// * `addr` - the address to listen at
+pub fn serve<A: ToSocketAddrs>(addr: A) -> Result<(), PvError> {
+    let server = Server::http(addr).map_err(|err| {
+        PvError::InternalError(format!("The HTTP server could not be started: {}", err))
+    })?;
+    for request in server.incoming_requests() {
+        if let Err(err) = handle_request(request) {
+            eprintln!("The HTTP request could not be answered: {:?}", err);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the simulation requested and responds with the simulated records or an error message.
+/// Fails if the response cannot be sent.
+///
+/// # Parameters
+///
+/// * `request` - the HTTP request
+fn handle_request(mut request: Request) -> Result<(), PvError> {
+    let (status_code, body) = if *request.method() != Method::Post {
+        (405, "Only POST requests are supported.".to_string())
+    } else {
+        let mut content = String::new();
+        match request.as_reader().read_to_string(&mut content) {
+            Ok(_) => match serde_json::from_str::<SimulationParameters>(&content) {
+                Ok(parameters) => match parameters.simulation_time() {
+                    Ok(simulation_time) => match simulate(parameters, simulation_time) {
+                        Ok(records) => (200, records),
+                        Err(err) => (500, format!("The simulation failed: {:?}", err)),
+                    },
+                    Err(err) => (400, format!("Invalid simulation parameters: {:?}", err)),
+                },
+                Err(err) => (400, format!("Invalid simulation parameters: {}", err)),
+            },
+            Err(err) => (400, format!("The request body could not be read: {}", err)),
+        }
+    };
+    let content_type = if status_code == 200 {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    // The header is statically known to be valid.
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    request.respond(
+        Response::from_string(body)
+            .with_status_code(StatusCode(status_code))
+            .with_header(header),
+    )?;
+    Ok(())
+}
+
+/// Simulates the `Meter` and photovoltaic component without a message broker
+/// and returns the records serialised as JSON.
+/// Fails if the consumption bound is invalid.
+///
+/// # Parameters
+///
+/// * `parameters` - the parameters of the simulation
+/// * `simulation_time` - the validated stride and length of the simulation
+fn simulate(
+    parameters: SimulationParameters,
+    simulation_time: (Duration, Duration),
+) -> Result<String, PvError> {
+    let (stride, simulation_length) = simulation_time;
+    let (meter, mut simulator) = match parameters.seed {
+        Some(seed) => {
+            let seeds = SeedSequence::new(seed);
+            (
+                Meter::with_seed(parameters.consumption_bound, "", seeds.meter_seed())?,
+                PvSimulator::with_seed("", seeds.pv_simulator_seed()),
+            )
+        },
+        None => (
+            Meter::new(parameters.consumption_bound, "")?,
+            PvSimulator::new(""),
+        ),
+    };
+    simulate_pv_in_memory(
+        &meter,
+        &mut simulator,
+        SimulatedDateTime::new(stride, simulation_length),
+    )?;
+    Ok(serde_json::to_string(simulator.records())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::photovoltaic_simulator::Record;
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    /// Sends a HTTP request to the specified address and returns the response.
+    /// Retries connecting until the server accepts connections.
+    ///
+    /// # Parameters
+    ///
+    /// * `addr` - the address of the server
+    /// * `method` - the HTTP method
+    /// * `body` - the body of the request
+    fn send_request<B: AsRef<[u8]>>(addr: &str, method: &str, body: B) -> String {
+        let body = body.as_ref();
+        let mut stream = None;
+        for _ in 0..100 {
+            match TcpStream::connect(addr) {
+                Ok(connection) => {
+                    stream = Some(connection);
+                    break;
+                },
+                Err(_) => thread::sleep(std::time::Duration::from_millis(50)),
+            }
+        }
+        let mut stream = stream.expect("The server did not accept connections.");
+        write!(
+            stream,
+            "{} / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            addr,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    /// Tests if the function `serve` answers simulation requests with records.
+    fn test_serve() {
+        let addr = "127.0.0.1:38754";
+        thread::spawn(move || serve(addr));
+        // Valid requests.
+        {
+            let body = r#"{"stride_in_sec": 3600.0, "simulation_length_in_h": 3.0, "seed": 42}"#;
+            let response = send_request(addr, "POST", body);
+            assert!(response.starts_with("HTTP/1.1 200"));
+            let content = &response[response.find("\r\n\r\n").unwrap() + 4..];
+            let records: Vec<Record> = serde_json::from_str(content).unwrap();
+            assert_eq!(records.len(), 3);
+            assert!(records
+                .iter()
+                .all(|record| record._power_consumption() < DEFAULT_CONSUMPTION_BOUND));
+            // Seeded simulations are reproducible apart from the starting time.
+            let repeated_response = send_request(addr, "POST", body);
+            let repeated_content =
+                &repeated_response[repeated_response.find("\r\n\r\n").unwrap() + 4..];
+            let repeated_records: Vec<Record> = serde_json::from_str(repeated_content).unwrap();
+            for (record, repeated_record) in records.iter().zip(repeated_records.iter()) {
+                assert_eq!(
+                    record._power_consumption(),
+                    repeated_record._power_consumption()
+                );
+            }
+        }
+        // Invalid requests.
+        assert!(send_request(addr, "GET", "").starts_with("HTTP/1.1 405"));
+        assert!(send_request(addr, "POST", "{}").starts_with("HTTP/1.1 400"));
+        let body = r#"{"stride_in_sec": -1.0, "simulation_length_in_h": 3.0}"#;
+        assert!(send_request(addr, "POST", body).starts_with("HTTP/1.1 400"));
+        let body = r#"{"stride_in_sec": 0.001, "simulation_length_in_h": 8760.0}"#;
+        assert!(send_request(addr, "POST", body).starts_with("HTTP/1.1 400"));
+        // The server keeps serving after a body that is not valid UTF-8.
+        assert!(send_request(addr, "POST", [0xff, 0xfe]).starts_with("HTTP/1.1 400"));
+        let body = r#"{"stride_in_sec": 3600.0, "simulation_length_in_h": 1.0}"#;
+        assert!(send_request(addr, "POST", body).starts_with("HTTP/1.1 200"));
+    }
+}
@@ -0,0 +1,127 @@
+//! The `message_format` module allows serialisation of broker messages in different
+//! wire formats.
+extern crate prost;
+
+use super::meter::BrokerMessage;
+use super::pv_error::PvError;
+use prost::Message;
+
+/// The `MessageFormat`s broker messages can be serialised in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MessageFormat {
+    /// Human readable JSON.
+    Json,
+    /// Compact Protocol Buffers.
+    Protobuf,
+}
+
+impl MessageFormat {
+    /// Returns the message format matching the specified content type property
+    /// of a broker message, if any.
+    ///
+    /// # Parameters
+    ///
+    /// * `content_type` - the content type of the message
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "application/json" => Some(MessageFormat::Json),
+            "application/x-protobuf" => Some(MessageFormat::Protobuf),
+            _ => None,
+        }
+    }
+
+    /// Returns the content type property of broker messages serialised in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            MessageFormat::Json => "application/json",
+            MessageFormat::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    /// Serialises the specified message.
+    /// Fails if the serialisation fails.
+    ///
+    /// # Parameters
+    ///
+    /// * `message` - the message to serialise
+    pub fn serialise(&self, message: &BrokerMessage) -> Result<Vec<u8>, PvError> {
+        match self {
+            // Floating point values are parsed with full precision, as the `float_roundtrip`
+            // feature of serde_json is enabled, so messages can be replayed exactly.
+            MessageFormat::Json => Ok(serde_json::to_vec(message)?),
+            MessageFormat::Protobuf => Ok(message.to_protobuf().encode_to_vec()),
+        }
+    }
+
+    /// Deserialises a message from the specified data.
+    /// Fails if the data is not a valid message in this format.
+    ///
+    /// # Parameters
+    ///
+    /// * `data` - the serialised message
+    pub fn deserialise(&self, data: &[u8]) -> Result<BrokerMessage, PvError> {
+        match self {
+            MessageFormat::Json => Ok(serde_json::from_slice(data)?),
+            MessageFormat::Protobuf => {
+                let message = ProtobufBrokerMessage::decode(data).map_err(|err| {
+                    PvError::InternalError(format!("Invalid protobuf message: {}", err))
+                })?;
+                BrokerMessage::from_protobuf(message)
+            },
+        }
+    }
+}
+
+/// The Protocol Buffers representation of a `BrokerMessage` as specified by:
+///
+/// ```protobuf
+/// message BrokerMessage {
+///   optional double power_consumption = 1;
+///   optional int64 time_stamp_seconds = 2;
+///   uint32 time_stamp_nanos = 3;
+///   repeated double phase_consumption = 4;
+/// }
+/// ```
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ProtobufBrokerMessage {
+    /// The power consumption in watt, if any.
+    #[prost(double, optional, tag = "1")]
+    pub power_consumption: Option<f64>,
+    /// The seconds of the time stamp since the UNIX epoch, if any.
+    #[prost(int64, optional, tag = "2")]
+    pub time_stamp_seconds: Option<i64>,
+    /// The nanoseconds of the time stamp within its second.
+    #[prost(uint32, tag = "3")]
+    pub time_stamp_nanos: u32,
+    /// The power consumption in watt of each phase, which is empty for single-phase meters.
+    #[prost(double, repeated, tag = "4")]
+    pub phase_consumption: Vec<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    /// Tests if messages are restored by deserialisation in each format.
+    fn test_serialise_deserialise() {
+        let messages = vec![
+            BrokerMessage::new(1234.5, Utc::now()).unwrap(),
+            BrokerMessage::three_phase([1.0, 2.5, 0.0], Utc::now()).unwrap(),
+            BrokerMessage::simulation_end_message(),
+        ];
+        for format in &[MessageFormat::Json, MessageFormat::Protobuf] {
+            assert_eq!(
+                MessageFormat::from_content_type(format.content_type()),
+                Some(*format)
+            );
+            for message in &messages {
+                let serialised = format.serialise(message).unwrap();
+                assert_eq!(format.deserialise(&serialised).unwrap(), *message);
+            }
+        }
+        assert_eq!(MessageFormat::from_content_type("text/plain"), None);
+        assert!(MessageFormat::Protobuf.deserialise(&[0xff; 8]).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! The `message_source` module abstracts the source of the messages processed by a
+//! `PvSimulator`, so processing does not depend on a message broker.
+
+use super::compression::Compression;
+use super::message_format::MessageFormat;
+use super::meter::BrokerMessage;
+use super::pv_error::PvError;
+use amiquip::{AmqpProperties, Consumer, ConsumerMessage, Exchange, Publish};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// The interval in which a `BrokerMessageSource` checks whether it was cancelled while
+/// waiting for the next message.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A `MessageSource` provides the messages a `PvSimulator` processes.
+pub trait MessageSource {
+    /// Returns the next message or `None` if the source is exhausted.
+    /// Fails if the next message cannot be obtained.
+    fn next_message(&mut self) -> Result<Option<BrokerMessage>, PvError>;
+
+    /// Returns the next message or `None` if the source is exhausted or no message
+    /// became available within the timeout.
+    /// Per default the next message is returned immediately without waiting.
+    /// Fails if the next message cannot be obtained.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - the maximum time to wait for the next message
+    fn next_message_within(
+        &mut self,
+        _timeout: std::time::Duration,
+    ) -> Result<Option<BrokerMessage>, PvError> {
+        self.next_message()
+    }
+
+    /// Handles a message that was obtained from the source, but could not be processed,
+    /// e.g. as it could not be converted to a `Record`.
+    /// Per default processing fails with the error of the message.
+    /// Fails if processing the remaining messages must not continue.
+    ///
+    /// # Parameters
+    ///
+    /// * `message` - the message that could not be processed
+    /// * `error` - the reason the message could not be processed
+    fn reject(&mut self, _message: BrokerMessage, error: PvError) -> Result<(), PvError> {
+        Err(error)
+    }
+}
+
+impl MessageSource for VecDeque<BrokerMessage> {
+    fn next_message(&mut self) -> Result<Option<BrokerMessage>, PvError> {
+        Ok(self.pop_front())
+    }
+}
+
+/// A `MessageDecoder` decompresses and deserialises message bodies recieved from the broker.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct MessageDecoder {
+    /// The compression assumed for messages without a content encoding property.
+    pub compression: Option<Compression>,
+    /// The wire format assumed for messages without a content type property.
+    pub message_format: MessageFormat,
+}
+
+impl MessageDecoder {
+    /// Decompresses and deserialises a message body recieved from the broker.
+    /// Fails if the message body is malformed, lacks the time stamp of a power consumption
+    /// or the content encoding or type is unknown.
+    ///
+    /// # Parameters
+    ///
+    /// * `body` - the serialised message from the broker
+    /// * `content_encoding` - the content encoding property of the message, if any
+    /// * `content_type` - the content type property of the message, if any
+    pub fn decode(
+        &self,
+        body: &[u8],
+        content_encoding: Option<&str>,
+        content_type: Option<&str>,
+    ) -> Result<BrokerMessage, PvError> {
+        let compression = match content_encoding {
+            Some(encoding) => {
+                Some(Compression::from_content_encoding(encoding).ok_or_else(|| {
+                    PvError::InternalError(format!("Unknown content encoding: {}", encoding))
+                })?)
+            },
+            None => self.compression,
+        };
+        let message_format = match content_type {
+            Some(content_type) => {
+                MessageFormat::from_content_type(content_type).ok_or_else(|| {
+                    PvError::InternalError(format!("Unknown content type: {}", content_type))
+                })?
+            },
+            None => self.message_format,
+        };
+        let message = match compression {
+            Some(compression) => message_format.deserialise(&compression.decompress(body)?)?,
+            None => message_format.deserialise(body)?,
+        };
+        if !message.is_simulation_end() && message.time_stamp().is_none() {
+            return Err(PvError::InternalError(format!(
+                "No time stamp was specified for message: {:?}",
+                message
+            )));
+        }
+        Ok(message)
+    }
+}
+
+/// A `BrokerMessageSource` consumes the messages from a queue of the message broker.
+/// Every message is acknowledged once it was obtained.
+pub(crate) struct BrokerMessageSource<'a> {
+    consumer: Consumer<'a>,
+    exchange: Exchange<'a>,
+    decoder: MessageDecoder,
+    dead_letter_queue: Option<String>,
+    cancellation: Option<&'a AtomicBool>,
+}
+
+impl<'a> BrokerMessageSource<'a> {
+    /// Creates a new `BrokerMessageSource`.
+    ///
+    /// # Parameters
+    ///
+    /// * `consumer` - the consumer of the queue
+    /// * `exchange` - the exchange used for republishing to the dead letter queue
+    /// * `decoder` - the decoder of the message bodies
+    /// * `dead_letter_queue` - the queue messages that cannot be decoded or processed are
+    ///   republished to, if any
+    pub fn new(
+        consumer: Consumer<'a>,
+        exchange: Exchange<'a>,
+        decoder: MessageDecoder,
+        dead_letter_queue: Option<String>,
+    ) -> Self {
+        BrokerMessageSource {
+            consumer,
+            exchange,
+            decoder,
+            dead_letter_queue,
+            cancellation: None,
+        }
+    }
+
+    /// Stops waiting for messages once the specified flag is set, so the source appears
+    /// exhausted.
+    ///
+    /// # Parameters
+    ///
+    /// * `cancellation` - the flag signaling cancellation
+    pub fn with_cancellation(mut self, cancellation: &'a AtomicBool) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+}
+
+impl BrokerMessageSource<'_> {
+    /// Returns `true` if the source was cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .is_some_and(|cancellation| cancellation.load(Ordering::SeqCst))
+    }
+
+    /// Waits for the next message of the consumer.
+    /// Returns `None` if the source was cancelled, the connection was closed or the deadline
+    /// passed.
+    ///
+    /// # Parameters
+    ///
+    /// * `deadline` - the point in time to stop waiting, if any
+    fn wait_for_message(&self, deadline: Option<Instant>) -> Option<ConsumerMessage> {
+        loop {
+            if self.is_cancelled() {
+                return None;
+            }
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let wait = match (remaining, self.cancellation) {
+                (Some(remaining), Some(_)) => Some(remaining.min(CANCELLATION_POLL_INTERVAL)),
+                (Some(remaining), None) => Some(remaining),
+                (None, Some(_)) => Some(CANCELLATION_POLL_INTERVAL),
+                (None, None) => None,
+            };
+            match wait {
+                Some(wait) => match self.consumer.receiver().recv_timeout(wait) {
+                    Ok(message) => return Some(message),
+                    Err(err) if err.is_timeout() => {
+                        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                            return None;
+                        }
+                    },
+                    Err(_) => return None,
+                },
+                None => return self.consumer.receiver().recv().ok(),
+            }
+        }
+    }
+
+    /// Returns the next message that can be decoded or `None` if the consumer or source was
+    /// cancelled, the connection was closed or the timeout elapsed.
+    /// Fails if a message cannot be decoded and there is no dead letter queue.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - the maximum time to wait for the next message, if any
+    fn receive(&mut self, timeout: Option<Duration>) -> Result<Option<BrokerMessage>, PvError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match self.wait_for_message(deadline) {
+                Some(ConsumerMessage::Delivery(delivery)) => {
+                    let content_encoding = delivery.properties.content_encoding().as_deref();
+                    let content_type = delivery.properties.content_type().as_deref();
+                    match self
+                        .decoder
+                        .decode(&delivery.body, content_encoding, content_type)
+                    {
+                        Ok(message) => {
+                            self.consumer.ack(delivery)?;
+                            return Ok(Some(message));
+                        },
+                        // Messages that cannot be decoded are skipped after republishing
+                        // them to the dead letter queue.
+                        Err(err) => match &self.dead_letter_queue {
+                            Some(dead_letter_queue) => {
+                                self.exchange.publish(Publish::with_properties(
+                                    &delivery.body,
+                                    dead_letter_queue.as_str(),
+                                    delivery.properties.clone(),
+                                ))?;
+                                self.consumer.ack(delivery)?;
+                            },
+                            None => return Err(err),
+                        },
+                    }
+                },
+                Some(ConsumerMessage::ClientCancelled) => return Ok(None),
+                Some(other) => {
+                    return Err(PvError::InternalError(format!(
+                        "Consumer did not expect: {:?}",
+                        other
+                    )))
+                },
+                // The source was cancelled, the connection to the broker was closed or
+                // the timeout elapsed.
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl MessageSource for BrokerMessageSource<'_> {
+    fn next_message(&mut self) -> Result<Option<BrokerMessage>, PvError> {
+        self.receive(None)
+    }
+
+    fn next_message_within(&mut self, timeout: Duration) -> Result<Option<BrokerMessage>, PvError> {
+        self.receive(Some(timeout))
+    }
+
+    /// Republishes the message to the dead letter queue in the wire format of the decoder,
+    /// as the original message body was already acknowledged.
+    /// Fails if there is no dead letter queue or the message cannot be republished.
+    fn reject(&mut self, message: BrokerMessage, error: PvError) -> Result<(), PvError> {
+        match &self.dead_letter_queue {
+            Some(dead_letter_queue) => {
+                let message_format = self.decoder.message_format;
+                let properties = AmqpProperties::default()
+                    .with_content_type(message_format.content_type().to_string());
+                self.exchange.publish(Publish::with_properties(
+                    &message_format.serialise(&message)?,
+                    dead_letter_queue.as_str(),
+                    properties,
+                ))?;
+                Ok(())
+            },
+            None => Err(error),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! The `aggregation` module allows a `PvSimulator` to process the messages of several
+//! `Meter`s, whose power consumption is summed per time stamp.
+
+use super::super::meter::BrokerMessage;
+use super::super::pv_error::PvError;
+use super::PvSimulator;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+/// The `MeterAggregation` sums the messages of several `Meter`s with the same time stamp
+/// and tracks the simulation end messages of all `Meter`s.
+#[derive(Debug)]
+pub(super) struct MeterAggregation {
+    meter_count: usize,
+    pending: BTreeMap<DateTime<Utc>, (BrokerMessage, usize)>,
+    ended: usize,
+}
+
+impl MeterAggregation {
+    /// Creates a new `MeterAggregation` of the messages of the specified number of `Meter`s.
+    ///
+    /// # Parameters
+    ///
+    /// * `meter_count` - the number of `Meter`s publishing messages
+    pub fn new(meter_count: usize) -> Self {
+        MeterAggregation {
+            meter_count,
+            pending: BTreeMap::new(),
+            ended: 0,
+        }
+    }
+
+    /// Returns `true` if a simulation end message was received from every `Meter`.
+    pub fn is_complete(&self) -> bool {
+        self.ended >= self.meter_count
+    }
+
+    /// Adds the message and returns the summed messages of all time stamps that were
+    /// received from every `Meter` in chronological order.
+    /// As every `Meter` publishes in chronological order, earlier time stamps are not
+    /// going to be completed anymore and are returned as well.
+    /// Fails if the summed power consumption is invalid, in which case the message is not
+    /// added.
+    ///
+    /// # Parameters
+    ///
+    /// * `message` - the received message
+    pub fn push(&mut self, message: BrokerMessage) -> Result<Vec<BrokerMessage>, PvError> {
+        if message.is_simulation_end() {
+            self.ended += 1;
+            return Ok(Vec::new());
+        }
+        let time_stamp = match message.time_stamp() {
+            // Messages of a single meter and invalid messages are processed as they are.
+            Some(time_stamp) if self.meter_count > 1 => time_stamp,
+            _ => return Ok(vec![message]),
+        };
+        let received = match self.pending.remove(&time_stamp) {
+            Some((sum, count)) => match sum_messages(sum, message, time_stamp) {
+                Ok(summed) => (summed, count + 1),
+                Err(err) => {
+                    // The messages received before are retained.
+                    self.pending.insert(time_stamp, (sum, count));
+                    return Err(err);
+                },
+            },
+            None => (message, 1),
+        };
+        self.pending.insert(time_stamp, received);
+        if received.1 < self.meter_count {
+            return Ok(Vec::new());
+        }
+        let later = self
+            .pending
+            .split_off(&(time_stamp + Duration::nanoseconds(1)));
+        let completed = std::mem::replace(&mut self.pending, later);
+        Ok(completed
+            .into_values()
+            .map(|(message, _)| message)
+            .collect())
+    }
+
+    /// Returns the summed messages of all remaining time stamps in chronological order,
+    /// e.g. if a `Meter` ended early.
+    pub fn finish(&mut self) -> Vec<BrokerMessage> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .map(|(message, _)| message)
+            .collect()
+    }
+}
+
+/// Returns a message with the summed power consumption of both messages.
+/// The consumption per phase is only retained if both messages are three-phase messages.
+/// Fails if the summed power consumption is invalid.
+///
+/// # Parameters
+///
+/// * `first` - the first message
+/// * `second` - the second message
+/// * `time_stamp` - the shared time stamp of both messages
+fn sum_messages(
+    first: BrokerMessage,
+    second: BrokerMessage,
+    time_stamp: DateTime<Utc>,
+) -> Result<BrokerMessage, PvError> {
+    match (first.phase_consumption(), second.phase_consumption()) {
+        (Some(mut phases), Some(other_phases)) => {
+            for (phase, other_phase) in phases.iter_mut().zip(other_phases.iter()) {
+                *phase += other_phase;
+            }
+            BrokerMessage::three_phase(phases, time_stamp)
+        },
+        _ => BrokerMessage::new(
+            first.power_consumption().unwrap_or(0.0) + second.power_consumption().unwrap_or(0.0),
+            time_stamp,
+        ),
+    }
+}
+
+impl PvSimulator {
+    /// Sets the number of `Meter`s publishing messages to the `PvSimulator`.
+    /// The power consumption of all `Meter`s is summed per time stamp into a single `Record`
+    /// and the simulation ends once a simulation end message was received from every `Meter`.
+    /// Per default a single `Meter` is expected.
+    /// Fails if the number of `Meter`s is zero.
+    ///
+    /// # Parameters
+    ///
+    /// * `meter_count` - the number of `Meter`s publishing messages
+    pub fn set_meter_count(&mut self, meter_count: usize) -> Result<(), PvError> {
+        if meter_count == 0 {
+            return Err(PvError::InternalError(
+                "At least one meter must publish messages.".to_string(),
+            ));
+        }
+        self.meter_count = meter_count;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::Meter;
+    use super::super::super::simulated_time::SimulatedDateTime;
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::VecDeque;
+
+    #[test]
+    /// Tests if the consumption of two `Meter`s is summed per time stamp and the simulation
+    /// only ends after both `Meter`s ended.
+    fn test_set_meter_count() {
+        let mut simulator = PvSimulator::with_seed("", 42);
+        assert!(simulator.set_meter_count(0).is_err());
+        simulator.set_meter_count(2).unwrap();
+        let meters = [
+            Meter::with_seed(1000.0, "", 1).unwrap(),
+            Meter::with_seed(1000.0, "", 2).unwrap(),
+        ];
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let simulation_time =
+            SimulatedDateTime::starting_at(start, Duration::minutes(1), Duration::days(1));
+        let mut expected = Vec::new();
+        let mut messages = VecDeque::new();
+        for time_point in simulation_time {
+            let first = meters[0].sample_message(time_point).unwrap();
+            let second = meters[1].sample_message(time_point).unwrap();
+            expected.push(first.power_consumption().unwrap() + second.power_consumption().unwrap());
+            messages.push_back(first);
+            messages.push_back(second);
+        }
+        // The first meter ends before the last message of the second one.
+        let last = messages.pop_back().unwrap();
+        messages.push_back(BrokerMessage::simulation_end_message());
+        messages.push_back(last);
+        messages.push_back(BrokerMessage::simulation_end_message());
+        // Messages after the end of the simulation are not processed.
+        messages.push_back(BrokerMessage::new(500.0, start).unwrap());
+        simulator.process_source(&mut messages).unwrap();
+        assert_eq!(messages.len(), 1);
+        let consumption: Vec<f64> = simulator
+            .records()
+            .iter()
+            .map(|record| record.meter_power_consumption)
+            .collect();
+        assert_eq!(consumption.len(), 1440);
+        for (consumption, expected) in consumption.iter().zip(expected.iter()) {
+            assert!((consumption - expected).abs() < 1e-9);
+        }
+        assert!(consumption.iter().all(|consumption| *consumption < 2000.0));
+        assert!(consumption.iter().any(|consumption| *consumption > 1000.0));
+    }
+
+    #[test]
+    /// Tests if time stamps missing from a `Meter` are processed in chronological order.
+    fn test_meter_aggregation_incomplete() {
+        let mut aggregation = MeterAggregation::new(2);
+        let time = |minute| Utc.with_ymd_and_hms(2021, 6, 21, 12, minute, 0).unwrap();
+        assert!(aggregation
+            .push(BrokerMessage::new(100.0, time(0)).unwrap())
+            .unwrap()
+            .is_empty());
+        assert!(aggregation
+            .push(BrokerMessage::new(200.0, time(1)).unwrap())
+            .unwrap()
+            .is_empty());
+        let completed = aggregation
+            .push(BrokerMessage::new(300.0, time(1)).unwrap())
+            .unwrap();
+        assert_eq!(
+            completed,
+            vec![
+                BrokerMessage::new(100.0, time(0)).unwrap(),
+                BrokerMessage::new(500.0, time(1)).unwrap()
+            ]
+        );
+        aggregation
+            .push(BrokerMessage::new(400.0, time(2)).unwrap())
+            .unwrap();
+        assert!(!aggregation.is_complete());
+        aggregation
+            .push(BrokerMessage::simulation_end_message())
+            .unwrap();
+        aggregation
+            .push(BrokerMessage::simulation_end_message())
+            .unwrap();
+        assert!(aggregation.is_complete());
+        assert_eq!(
+            aggregation.finish(),
+            vec![BrokerMessage::new(400.0, time(2)).unwrap()]
+        );
+    }
+}
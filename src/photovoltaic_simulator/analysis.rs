@@ -0,0 +1,1744 @@
+//! The `analysis` module allows evaluation of the `Record`s observed by a `PvSimulator`.
+
+use super::{validate_model_output, PvSimulator, Record, RecordQuality};
+use chrono::{DateTime, Duration, NaiveDate, Offset, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `Aggregation` functions available to combine the values of multiple `Record`s
+/// during resampling.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Aggregation {
+    /// The arithmetic mean, which is appropriate for power values.
+    Mean,
+    /// The maximum value.
+    Max,
+    /// The minimum value.
+    Min,
+    /// The sum of all values, which is appropriate for energy values.
+    Sum,
+    /// The chronologically last value.
+    Last,
+}
+
+impl Aggregation {
+    /// Combines the specified values. Returns `None` if there are no values.
+    ///
+    /// # Parameters
+    ///
+    /// * `values` - the values in chronological order
+    fn apply<I: Iterator<Item = f64>>(&self, values: I) -> Option<f64> {
+        let mut count = 0;
+        let aggregate = values.fold(None, |aggregate: Option<f64>, value| {
+            count += 1;
+            Some(match (self, aggregate) {
+                (_, None) => value,
+                (Aggregation::Mean, Some(sum)) | (Aggregation::Sum, Some(sum)) => sum + value,
+                (Aggregation::Max, Some(max)) => max.max(value),
+                (Aggregation::Min, Some(min)) => min.min(value),
+                (Aggregation::Last, Some(_)) => value,
+            })
+        });
+        match self {
+            Aggregation::Mean => aggregate.map(|sum| sum / count as f64),
+            _ => aggregate,
+        }
+    }
+}
+
+/// The `EnergySummary` of all `Record`s in kWh, where the power values of every `Record`
+/// are assumed to persist until the next `Record`.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct EnergySummary {
+    /// The energy consumed according to the `Meter`.
+    pub consumed_kwh: f64,
+    /// The AC energy produced by the photovoltaic component.
+    pub produced_kwh: f64,
+    /// The produced minus the consumed energy.
+    pub net_kwh: f64,
+    /// The energy imported from the grid while the consumption exceeds the production.
+    pub grid_import_kwh: f64,
+    /// The energy exported to the grid while the production exceeds the consumption.
+    pub grid_export_kwh: f64,
+}
+
+/// The `SimulationSummary` combines the key metrics of all `Record`s into a compact overview.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct SimulationSummary {
+    /// The AC energy produced by the photovoltaic component in kWh.
+    pub total_energy_kwh: f64,
+    /// The peak AC power output in watt.
+    pub peak_output_w: f64,
+    /// The time of the first `Record` with the peak power output, if any.
+    pub peak_output_time: Option<DateTime<Utc>>,
+    /// The fraction of the produced energy consumed locally instead of being exported.
+    pub self_consumption_ratio: f64,
+    /// The number of `Record`s.
+    pub record_count: usize,
+    /// The time stamp of the first `Record`, if any.
+    pub start_time: Option<DateTime<Utc>>,
+    /// The time stamp of the last `Record`, if any.
+    pub end_time: Option<DateTime<Utc>>,
+    /// The statistics of the power consumption according to the `Meter`, if any.
+    #[serde(default)]
+    pub meter_power_consumption: Option<PowerStatistics>,
+    /// The statistics of the AC power output of the photovoltaic component, if any.
+    #[serde(default)]
+    pub pv_power_output: Option<PowerStatistics>,
+    /// The statistics of the total power output, if any.
+    #[serde(default)]
+    pub total_power_output: Option<PowerStatistics>,
+}
+
+/// The `PowerStatistics` of a power value over all `Record`s in watt.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct PowerStatistics {
+    /// The minimum value.
+    pub min: f64,
+    /// The maximum value.
+    pub max: f64,
+    /// The arithmetic mean.
+    pub mean: f64,
+}
+
+impl PowerStatistics {
+    /// Returns the `PowerStatistics` of the specified values or `None` if there are no values.
+    ///
+    /// # Parameters
+    ///
+    /// * `values` - the power values
+    fn of<I: Iterator<Item = f64> + Clone>(values: I) -> Option<Self> {
+        Some(PowerStatistics {
+            min: Aggregation::Min.apply(values.clone())?,
+            max: Aggregation::Max.apply(values.clone())?,
+            mean: Aggregation::Mean.apply(values)?,
+        })
+    }
+}
+
+/// The `DailySummary` combines the key metrics of the `Record`s of a single day.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct DailySummary {
+    /// The UTC date of the day.
+    pub date: NaiveDate,
+    /// The energy consumed according to the `Meter` in kWh.
+    pub consumed_kwh: f64,
+    /// The AC energy produced by the photovoltaic component in kWh.
+    pub produced_kwh: f64,
+    /// The peak AC power output in watt.
+    pub peak_output_w: f64,
+    /// The time of the first `Record` with the peak power output.
+    pub peak_output_time: DateTime<Utc>,
+    /// The fraction of the produced energy consumed locally instead of being exported.
+    pub self_consumption_ratio: f64,
+}
+
+/// The fraction of the theoretical maximum yield below which the yield is implausibly low.
+const MINIMUM_PLAUSIBLE_YIELD_RATIO: f64 = 0.05;
+
+/// The `YieldStatus` classifies the plausibility of the energy produced during a simulation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum YieldStatus {
+    /// The yield is in a plausible range.
+    Plausible,
+    /// The yield is implausibly low, which indicates a misconfiguration.
+    ImplausiblyLow,
+}
+
+/// The `YieldSanity` reports if the energy produced during a simulation is plausible.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct YieldSanity {
+    /// The plausibility of the yield.
+    pub status: YieldStatus,
+    /// The ratio of the produced energy to the theoretical maximum.
+    pub ratio: f64,
+}
+
+/// The `RunDiff` reports the differences between the `Record`s of two simulation runs.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct RunDiff {
+    /// The maximum absolute difference of the power consumption in watt.
+    pub max_consumption_diff: f64,
+    /// The maximum absolute difference of the AC power output in watt.
+    pub max_output_diff: f64,
+    /// The maximum absolute difference of the total power output in watt.
+    pub max_total_diff: f64,
+    /// The maximum absolute difference of the DC power output in watt.
+    pub max_dc_output_diff: f64,
+    /// The number of `Record`s whose time stamps differ, including `Record`s without
+    /// counterpart in the other run.
+    pub mismatched_time_stamps: usize,
+}
+
+impl RunDiff {
+    /// Returns the maximum absolute difference of all power values in watt.
+    pub fn max_diff(&self) -> f64 {
+        self.max_consumption_diff
+            .max(self.max_output_diff)
+            .max(self.max_total_diff)
+            .max(self.max_dc_output_diff)
+    }
+
+    /// Checks if both runs have matching time stamps and all power values differ
+    /// by at most the specified tolerance.
+    ///
+    /// # Parameters
+    ///
+    /// * `tolerance` - the maximum accepted absolute difference in watt
+    pub fn is_within(&self, tolerance: f64) -> bool {
+        self.mismatched_time_stamps == 0 && self.max_diff() <= tolerance
+    }
+}
+
+/// Compares the `Record`s of two simulation runs pairwise in their order.
+///
+/// # Parameters
+///
+/// * `a` - the `Record`s of the first run
+/// * `b` - the `Record`s of the second run
+pub fn compare_runs(a: &[Record], b: &[Record]) -> RunDiff {
+    let mut diff = RunDiff {
+        max_consumption_diff: 0.0,
+        max_output_diff: 0.0,
+        max_total_diff: 0.0,
+        max_dc_output_diff: 0.0,
+        mismatched_time_stamps: a.len().max(b.len()) - a.len().min(b.len()),
+    };
+    for (first, second) in a.iter().zip(b) {
+        if first.time_stamp != second.time_stamp {
+            diff.mismatched_time_stamps += 1;
+        }
+        diff.max_consumption_diff = diff
+            .max_consumption_diff
+            .max((first.meter_power_consumption - second.meter_power_consumption).abs());
+        diff.max_output_diff = diff
+            .max_output_diff
+            .max((first.pv_power_output - second.pv_power_output).abs());
+        diff.max_total_diff = diff
+            .max_total_diff
+            .max((first.total_power_output - second.total_power_output).abs());
+        diff.max_dc_output_diff = diff
+            .max_dc_output_diff
+            .max((first.dc_output - second.dc_output).abs());
+    }
+    diff
+}
+
+/// Aggregates the `Record`s of multiple sites into the `Record`s of a virtual power plant.
+/// As the time stamps of the sites might be slightly misaligned, the `Record`s of each site
+/// are averaged within buckets of the specified duration (see `PvSimulator::resample`).
+/// The power values of all sites are then summed per bucket. Phase values are only retained
+/// if all sites contributing to a bucket specify them.
+///
+/// # Parameters
+///
+/// * `simulators` - the simulators of the individual sites
+/// * `bucket` - the duration of each bucket
+///
+/// # Panics
+///
+/// If the `bucket` duration is smaller or equal to zero.
+pub fn aggregate_sites(simulators: &[PvSimulator], bucket: Duration) -> Vec<Record> {
+    let sum_phase = |a: Option<f64>, b: Option<f64>| a.zip(b).map(|(a, b)| a + b);
+    let mut portfolio: BTreeMap<DateTime<Utc>, Record> = BTreeMap::new();
+    for simulator in simulators {
+        for record in simulator.resample(bucket, Aggregation::Mean) {
+            portfolio
+                .entry(record.time_stamp)
+                .and_modify(|total| {
+                    total.meter_power_consumption += record.meter_power_consumption;
+                    total.pv_power_output += record.pv_power_output;
+                    total.total_power_output += record.total_power_output;
+                    total.dc_output += record.dc_output;
+                    total.phase_a_consumption =
+                        sum_phase(total.phase_a_consumption, record.phase_a_consumption);
+                    total.phase_b_consumption =
+                        sum_phase(total.phase_b_consumption, record.phase_b_consumption);
+                    total.phase_c_consumption =
+                        sum_phase(total.phase_c_consumption, record.phase_c_consumption);
+                })
+                .or_insert(record);
+        }
+    }
+    portfolio.into_values().collect()
+}
+
+impl PvSimulator {
+    /// Returns the time points at which the total power output changes its sign.
+    /// `true` indicates that the power output starts to exceed the power consumption
+    /// (net export begins), `false` that the power consumption starts to exceed the
+    /// power output again (net import resumes). The sign of the first `Record` is taken
+    /// as initial state and is not reported itself.
+    pub fn net_zero_crossings(&self) -> Vec<(DateTime<Utc>, bool)> {
+        self.records
+            .windows(2)
+            .filter_map(|pair| {
+                let previous_export = pair[0].total_power_output > 0.0;
+                let current_export = pair[1].total_power_output > 0.0;
+                if previous_export == current_export {
+                    None
+                } else {
+                    Some((pair[1].time_stamp, current_export))
+                }
+            })
+            .collect()
+    }
+
+    /// Returns all `Record`s within the specified time window. Both bounds are inclusive,
+    /// so `Record`s exactly at `start` or `end` are part of the window.
+    /// If `end` is before `start`, the window is empty.
+    ///
+    /// # Parameters
+    ///
+    /// * `start` - the inclusive start of the time window
+    /// * `end` - the inclusive end of the time window
+    pub fn window(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Record> {
+        self.records
+            .iter()
+            .filter(|record| record.time_stamp >= start && record.time_stamp <= end)
+            .copied()
+            .collect()
+    }
+
+    /// Returns all distinct UTC dates the `Record`s span in chronological order.
+    pub fn distinct_dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = self
+            .records
+            .iter()
+            .map(|record| record.time_stamp.date_naive())
+            .collect();
+        dates.sort_unstable();
+        dates.dedup();
+        dates
+    }
+
+    /// Returns the `EnergySummary` of all `Record`s.
+    /// The power values are integrated by left rectangles, where the last `Record` is
+    /// assumed to persist for the preceding interval (see `record_intervals_in_h`). This rule
+    /// is shared by `hourly_yield_wh`, `specific_yield` and `daily_summaries` and splits the
+    /// energy exactly into grid import and export, but differs from the trapezoidal rule of
+    /// `total_consumed_kwh`, `total_generated_kwh` and `net_energy_kwh` whenever the power
+    /// changes between the first and the last `Record`.
+    pub fn energy_summary(&self) -> EnergySummary {
+        let [consumed_wh, produced_wh, grid_import_wh, grid_export_wh] = self.reduce_records(
+            || [0.0; 4],
+            |[consumed, produced, grid_import, grid_export], record, interval_in_h| {
+                [
+                    consumed + record.meter_power_consumption * interval_in_h,
+                    produced + record.pv_power_output * interval_in_h,
+                    grid_import + record.grid_import_w() * interval_in_h,
+                    grid_export + record.grid_export_w() * interval_in_h,
+                ]
+            },
+            |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]],
+        );
+        EnergySummary {
+            consumed_kwh: consumed_wh / 1000.0,
+            produced_kwh: produced_wh / 1000.0,
+            net_kwh: (produced_wh - consumed_wh) / 1000.0,
+            grid_import_kwh: grid_import_wh / 1000.0,
+            grid_export_kwh: grid_export_wh / 1000.0,
+        }
+    }
+
+    /// Returns the energy in kWh consumed according to the `Meter`, which is integrated over
+    /// the time stamps of the `Record`s by the trapezoidal rule, so irregular intervals are
+    /// weighted by their actual length.
+    /// In contrast to `energy_summary`, the trapezoidal rule only covers the time between the
+    /// first and the last `Record`, so both results differ slightly.
+    /// Returns zero if there are fewer than two `Record`s.
+    pub fn total_consumed_kwh(&self) -> f64 {
+        self.trapezoidal_kwh(|record| record.meter_power_consumption)
+    }
+
+    /// Returns the AC energy in kWh produced by the photovoltaic component, which is integrated
+    /// over the time stamps of the `Record`s by the trapezoidal rule (see
+    /// `total_consumed_kwh` for the difference to `energy_summary`).
+    /// Returns zero if there are fewer than two `Record`s.
+    pub fn total_generated_kwh(&self) -> f64 {
+        self.trapezoidal_kwh(|record| record.pv_power_output)
+    }
+
+    /// Returns the generated minus the consumed energy in kWh, which are integrated over the
+    /// time stamps of the `Record`s by the trapezoidal rule (see
+    /// `total_consumed_kwh` for the difference to `energy_summary`).
+    /// Returns zero if there are fewer than two `Record`s.
+    pub fn net_energy_kwh(&self) -> f64 {
+        self.total_generated_kwh() - self.total_consumed_kwh()
+    }
+
+    /// Integrates the specified power in watt over the time stamps of the `Record`s by the
+    /// trapezoidal rule and returns the energy in kWh.
+    ///
+    /// # Parameters
+    ///
+    /// * `power` - the function returning the power of a `Record`
+    fn trapezoidal_kwh<F: Fn(&Record) -> f64>(&self, power: F) -> f64 {
+        let energy_wh: f64 = self
+            .records
+            .windows(2)
+            .map(|pair| {
+                let interval_in_h = duration_in_h(pair[1].time_stamp - pair[0].time_stamp);
+                (power(&pair[0]) + power(&pair[1])) / 2.0 * interval_in_h
+            })
+            .sum();
+        energy_wh / 1000.0
+    }
+
+    /// Returns the `SimulationSummary` of all `Record`s.
+    /// The self-consumption ratio is zero if no energy was produced.
+    /// In contrast to the energy, the power statistics are not integrated and thus do not
+    /// depend on the time stamps of the `Record`s.
+    pub fn summary(&self) -> SimulationSummary {
+        let energy_summary = self.energy_summary();
+        let peak = peak_output(&self.records);
+        let records = self.records.iter();
+        let self_consumption_ratio =
+            self_consumption_ratio(energy_summary.produced_kwh, energy_summary.grid_export_kwh);
+        SimulationSummary {
+            total_energy_kwh: energy_summary.produced_kwh,
+            peak_output_w: peak.map_or(0.0, |peak| peak.pv_power_output),
+            peak_output_time: peak.map(|peak| peak.time_stamp),
+            self_consumption_ratio,
+            record_count: self.records.len(),
+            start_time: self.records.first().map(|record| record.time_stamp),
+            end_time: self.records.last().map(|record| record.time_stamp),
+            meter_power_consumption: PowerStatistics::of(
+                records.clone().map(|record| record.meter_power_consumption),
+            ),
+            pv_power_output: PowerStatistics::of(
+                records.clone().map(|record| record.pv_power_output),
+            ),
+            total_power_output: PowerStatistics::of(
+                records.map(|record| record.total_power_output),
+            ),
+        }
+    }
+
+    /// Returns the `DailySummary` of each UTC date with `Record`s in chronological order.
+    /// The power values of every `Record` are assumed to persist until the next `Record`,
+    /// even if it belongs to the next day.
+    pub fn daily_summaries(&self) -> Vec<DailySummary> {
+        let intervals_in_h = record_intervals_in_h(&self.records);
+        let mut summaries = Vec::new();
+        let mut day_start = 0;
+        while day_start < self.records.len() {
+            let date = self.records[day_start].time_stamp.date_naive();
+            let day_length = self.records[day_start..]
+                .iter()
+                .take_while(|record| record.time_stamp.date_naive() == date)
+                .count();
+            let day_end = day_start + day_length;
+            let mut consumed_wh = 0.0;
+            let mut produced_wh = 0.0;
+            let mut grid_export_wh = 0.0;
+            for (record, interval_in_h) in self.records[day_start..day_end]
+                .iter()
+                .zip(&intervals_in_h[day_start..day_end])
+            {
+                consumed_wh += record.meter_power_consumption * interval_in_h;
+                produced_wh += record.pv_power_output * interval_in_h;
+                grid_export_wh += record.grid_export_w() * interval_in_h;
+            }
+            // Every day contains at least one record.
+            let peak = peak_output(&self.records[day_start..day_end]).unwrap();
+            summaries.push(DailySummary {
+                date,
+                consumed_kwh: consumed_wh / 1000.0,
+                produced_kwh: produced_wh / 1000.0,
+                peak_output_w: peak.pv_power_output,
+                peak_output_time: peak.time_stamp,
+                self_consumption_ratio: self_consumption_ratio(produced_wh, grid_export_wh),
+            });
+            day_start = day_end;
+        }
+        summaries
+    }
+
+    /// Checks if the energy produced by the photovoltaic component is plausible.
+    /// The theoretical maximum is the output of the model of the power output (see
+    /// `set_model`) without any noise during the time covered by the `Record`s.
+    /// A yield below 5% of the theoretical maximum is implausibly low. If there is no
+    /// theoretical yield, e.g. as the capacity is zero, the ratio is zero and the yield is
+    /// reported as implausibly low.
+    pub fn yield_sanity_check(&self) -> YieldSanity {
+        let mut produced_wh = 0.0;
+        let mut expected_wh = 0.0;
+        for (record, interval_in_h) in self
+            .records
+            .iter()
+            .zip(record_intervals_in_h(&self.records))
+        {
+            produced_wh += record.pv_power_output * interval_in_h;
+            // Invalid output of the model is not expected.
+            expected_wh += validate_model_output(self.model.output_watts(record.time_stamp))
+                .unwrap_or(0.0)
+                * interval_in_h;
+        }
+        let ratio = if expected_wh > 0.0 {
+            produced_wh / expected_wh
+        } else {
+            0.0
+        };
+        let status = if ratio < MINIMUM_PLAUSIBLE_YIELD_RATIO {
+            YieldStatus::ImplausiblyLow
+        } else {
+            YieldStatus::Plausible
+        };
+        YieldSanity { status, ratio }
+    }
+
+    /// Returns every `factor`-th `Record` starting with the first one.
+    /// In contrast to averaging, the exact values of the retained `Record`s are preserved.
+    ///
+    /// # Parameters
+    ///
+    /// * `factor` - the decimation factor
+    ///
+    /// # Panics
+    ///
+    /// If the `factor` is zero.
+    pub fn decimate(&self, factor: usize) -> Vec<Record> {
+        if factor == 0 {
+            panic!("The decimation factor must be at least 1!");
+        }
+        self.records.iter().step_by(factor).copied().collect()
+    }
+
+    /// Returns the `Record`s resampled to buckets of the specified duration, where each power
+    /// value is combined by the aggregation function separately. Buckets are aligned to
+    /// multiples of the duration since the UNIX epoch and time stamped with their start.
+    /// Empty buckets are omitted. Phase values are only retained if all `Record`s within
+    /// a bucket specify them. The first quality other than good within a bucket is retained.
+    ///
+    /// # Parameters
+    ///
+    /// * `bucket` - the duration of each bucket
+    /// * `aggregation` - the function combining the values within a bucket
+    ///
+    /// # Panics
+    ///
+    /// If the `bucket` duration is smaller or equal to zero.
+    pub fn resample(&self, bucket: Duration, aggregation: Aggregation) -> Vec<Record> {
+        if bucket <= Duration::zero() {
+            panic!("The resampling bucket duration must be positive!");
+        }
+        let bucket_in_ms = bucket.num_milliseconds().max(1);
+        let mut resampled = Vec::new();
+        let mut remaining = &self.records[..];
+        while let Some(first) = remaining.first() {
+            let bucket_index = first.time_stamp.timestamp_millis().div_euclid(bucket_in_ms);
+            let bucket_size = remaining
+                .iter()
+                .take_while(|record| {
+                    record
+                        .time_stamp
+                        .timestamp_millis()
+                        .div_euclid(bucket_in_ms)
+                        == bucket_index
+                })
+                .count();
+            let (bucket_records, rest) = remaining.split_at(bucket_size);
+            remaining = rest;
+            // The bucket contains at least one record, so all aggregations succeed.
+            let aggregate = |value: fn(&Record) -> f64| {
+                aggregation.apply(bucket_records.iter().map(value)).unwrap()
+            };
+            let aggregate_phase = |value: fn(&Record) -> Option<f64>| {
+                bucket_records
+                    .iter()
+                    .map(value)
+                    .collect::<Option<Vec<f64>>>()
+                    .and_then(|values| aggregation.apply(values.into_iter()))
+            };
+            let time_stamp = DateTime::from_timestamp_millis(bucket_index * bucket_in_ms)
+                .unwrap_or(first.time_stamp);
+            resampled.push(Record {
+                time_stamp,
+                meter_power_consumption: aggregate(|record| record.meter_power_consumption),
+                pv_power_output: aggregate(|record| record.pv_power_output),
+                total_power_output: aggregate(|record| record.total_power_output),
+                dc_output: aggregate(|record| record.dc_output),
+                phase_a_consumption: aggregate_phase(|record| record.phase_a_consumption),
+                phase_b_consumption: aggregate_phase(|record| record.phase_b_consumption),
+                phase_c_consumption: aggregate_phase(|record| record.phase_c_consumption),
+                // The offset of the first record is retained for the bucket.
+                local_time: first
+                    .local_time
+                    .map(|local_time| time_stamp.with_timezone(local_time.offset())),
+                quality: bucket_records
+                    .iter()
+                    .map(|record| record.quality)
+                    .find(|quality| *quality != RecordQuality::Good)
+                    .unwrap_or_default(),
+            });
+        }
+        resampled
+    }
+
+    /// Returns the energy in Wh produced by the photovoltaic component in each hour of the day
+    /// summed over all simulated days. The power output of every `Record` is assumed to
+    /// persist until the next `Record` like in `energy_summary`.
+    pub fn hourly_yield_wh(&self) -> [f64; 24] {
+        self.reduce_records(
+            || [0.0; 24],
+            |mut hourly_yield, record, interval_in_h| {
+                hourly_yield[record.time_stamp.hour() as usize] +=
+                    record.pv_power_output * interval_in_h;
+                hourly_yield
+            },
+            |mut a, b| {
+                a.iter_mut().zip(b.iter()).for_each(|(a, b)| *a += b);
+                a
+            },
+        )
+    }
+
+    /// Returns the specific yield in kWh/kWp, which is the energy produced by the photovoltaic
+    /// component normalised by its installed capacity as specified by the peak scaling.
+    /// Returns zero if the installed capacity is zero or unknown, as the power output is
+    /// simulated by another model than the `KumaraswamyModel`.
+    pub fn specific_yield(&self) -> f64 {
+        let capacity_in_kwp = self.peak_scaling().unwrap_or(0.0) / 1000.0;
+        if capacity_in_kwp > 0.0 {
+            self.hourly_yield_wh().iter().sum::<f64>() / 1000.0 / capacity_in_kwp
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the rate of change of the power output in watt per second between subsequent
+    /// `Record`s, time stamped with the later `Record`.
+    /// `Record`s without time passing in between are skipped.
+    pub fn ramp_rates(&self) -> Vec<(DateTime<Utc>, f64)> {
+        self.records
+            .windows(2)
+            .filter_map(|pair| {
+                let interval_in_s = duration_in_h(pair[1].time_stamp - pair[0].time_stamp) * 3600.0;
+                if interval_in_s > 0.0 {
+                    Some((
+                        pair[1].time_stamp,
+                        (pair[1].pv_power_output - pair[0].pv_power_output) / interval_in_s,
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the largest magnitude of the total power output in watt regardless of whether
+    /// power is imported from or exported to the grid. Returns zero if there are no `Record`s.
+    pub fn peak_net_magnitude(&self) -> f64 {
+        self.records
+            .iter()
+            .map(|record| record.total_power_output.abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Returns the deviation of the net load in watt (power consumption minus power output)
+    /// of each `Record` from the rolling baseline, which is the average net load of all
+    /// `Record`s within the preceding `window`.
+    /// `Record`s within the first `window` of the simulation are omitted, as their baseline
+    /// is not fully populated. If the `window` is not positive, no deviations are returned.
+    ///
+    /// # Parameters
+    ///
+    /// * `window` - the time span preceding each `Record` its baseline is averaged over
+    pub fn deviation_from_baseline(&self, window: Duration) -> Vec<(DateTime<Utc>, f64)> {
+        let first_time = match self.records.first() {
+            Some(first) if window > Duration::zero() => first.time_stamp,
+            _ => return Vec::new(),
+        };
+        let mut deviations = Vec::new();
+        // The baseline records are all records from index `window_start` up to the current one.
+        let mut window_start = 0;
+        let mut window_sum = 0.0;
+        for (i, record) in self.records.iter().enumerate() {
+            while self.records[window_start].time_stamp < record.time_stamp - window {
+                window_sum -= self.records[window_start].net_load();
+                window_start += 1;
+            }
+            let window_size = i - window_start;
+            if record.time_stamp - first_time >= window && window_size > 0 {
+                let baseline = window_sum / window_size as f64;
+                deviations.push((record.time_stamp, record.net_load() - baseline));
+            }
+            window_sum += record.net_load();
+        }
+        deviations
+    }
+
+    /// Returns the coefficient of variation (standard deviation divided by mean) of the power
+    /// output during daylight. Only `Record`s with a positive power output are considered,
+    /// as the absent output at night would skew the result.
+    /// Returns zero if there are less than two daylight `Record`s.
+    pub fn production_cv(&self) -> f64 {
+        let daylight_outputs: Vec<f64> = self
+            .records
+            .iter()
+            .map(|record| record.pv_power_output)
+            .filter(|output| *output > 0.0)
+            .collect();
+        if daylight_outputs.len() < 2 {
+            return 0.0;
+        }
+        let n = daylight_outputs.len() as f64;
+        let mean = daylight_outputs.iter().sum::<f64>() / n;
+        let variance = daylight_outputs
+            .iter()
+            .map(|output| (output - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        variance.sqrt() / mean
+    }
+
+    /// Returns the average energy in kWh imported from the grid per simulated day, which is
+    /// the daily energy deficit not covered by the photovoltaic component.
+    /// Days are grouped by their UTC date and only days with `Record`s are considered.
+    /// Returns zero if there are no `Record`s.
+    pub fn average_daily_grid_import_kwh(&self) -> f64 {
+        let daily_import = self.daily_grid_import_kwh();
+        if daily_import.is_empty() {
+            0.0
+        } else {
+            daily_import.values().sum::<f64>() / daily_import.len() as f64
+        }
+    }
+
+    /// Returns the fraction of `Record`s with a positive power output, which approximates
+    /// the fraction of time the photovoltaic component is producing power.
+    /// Returns zero if there are no `Record`s.
+    pub fn producing_time_fraction(&self) -> f64 {
+        if self.records.is_empty() {
+            return 0.0;
+        }
+        let producing = self
+            .records
+            .iter()
+            .filter(|record| record.pv_power_output > 0.0)
+            .count();
+        producing as f64 / self.records.len() as f64
+    }
+
+    /// Returns the average power output in watt, where the output of each `Record` is weighted
+    /// by the interval to the next `Record`. In contrast to the arithmetic mean, this is robust
+    /// to non-uniform time stamps. If the `Record`s do not span any time, the arithmetic mean
+    /// is returned.
+    /// Returns zero if there are no `Record`s.
+    pub fn time_weighted_average_output(&self) -> f64 {
+        if self.records.is_empty() {
+            return 0.0;
+        }
+        let intervals_in_h = record_intervals_in_h(&self.records);
+        let total_in_h: f64 = intervals_in_h.iter().sum();
+        if total_in_h > 0.0 {
+            self.records
+                .iter()
+                .zip(intervals_in_h)
+                .map(|(record, interval_in_h)| record.pv_power_output * interval_in_h)
+                .sum::<f64>()
+                / total_in_h
+        } else {
+            self.records
+                .iter()
+                .map(|record| record.pv_power_output)
+                .sum::<f64>()
+                / self.records.len() as f64
+        }
+    }
+
+    /// Returns the energy in Wh produced by the photovoltaic component on the current day up to
+    /// and including the interval of each `Record`. The running total resets at each UTC
+    /// date boundary, so the last entry of each day is the total production of that day.
+    pub fn cumulative_energy_wh(&self) -> Vec<(DateTime<Utc>, f64)> {
+        let mut current_date = None;
+        let mut cumulative_wh = 0.0;
+        self.records
+            .iter()
+            .zip(record_intervals_in_h(&self.records))
+            .map(|(record, interval_in_h)| {
+                let date = record.time_stamp.date_naive();
+                if current_date != Some(date) {
+                    current_date = Some(date);
+                    cumulative_wh = 0.0;
+                }
+                cumulative_wh += record.pv_power_output * interval_in_h;
+                (record.time_stamp, cumulative_wh)
+            })
+            .collect()
+    }
+
+    /// Returns how well the power output matches the target load profile as 1 minus the root
+    /// mean square error normalised by the mean target load. A perfect match scores 1, while
+    /// the score decreases with increasing mismatch and may become negative.
+    /// The target load of each `Record` is specified at the same index. If the lengths differ,
+    /// only the `Record`s with a target load are compared.
+    /// Returns zero if there is nothing to compare or the mean target load is zero unless
+    /// the power output matches exactly.
+    ///
+    /// # Parameters
+    ///
+    /// * `target` - the target load in watt of each `Record`
+    pub fn load_match_score(&self, target: &[f64]) -> f64 {
+        let compared = self.records.len().min(target.len());
+        if compared == 0 {
+            return 0.0;
+        }
+        let squared_error: f64 = self
+            .records
+            .iter()
+            .zip(target)
+            .map(|(record, target_load)| (record.pv_power_output - target_load).powi(2))
+            .sum();
+        let rmse = (squared_error / compared as f64).sqrt();
+        let mean_target = target[..compared].iter().sum::<f64>() / compared as f64;
+        if rmse == 0.0 {
+            1.0
+        } else if mean_target == 0.0 {
+            0.0
+        } else {
+            1.0 - rmse / mean_target.abs()
+        }
+    }
+
+    /// Checks if the `Record`s span a daylight saving time transition of the timezone of the
+    /// site, which results in a missing or repeated hour of local time.
+    /// Returns `false` if no timezone is set.
+    pub fn crosses_dst(&self) -> bool {
+        self.timezone.is_some_and(|timezone| {
+            self.records.windows(2).any(|pair| {
+                pair[0].time_stamp.with_timezone(&timezone).offset().fix()
+                    != pair[1].time_stamp.with_timezone(&timezone).offset().fix()
+            })
+        })
+    }
+
+    /// Returns the shift of the power consumption within the specified maximum shift, which
+    /// maximises the energy consumed directly from the power output. Negative shifts move the
+    /// consumption to earlier time points and positive shifts to later ones.
+    /// The `Record`s are assumed to be equidistant, so shifts are multiples of the interval
+    /// between the first two `Record`s. Consumption shifted beyond the simulated time frame is
+    /// discarded. If multiple shifts perform equally well, the smallest shift is preferred.
+    /// Returns zero if there are fewer than two `Record`s.
+    ///
+    /// # Parameters
+    ///
+    /// * `max_shift` - the maximum absolute shift of the consumption
+    pub fn optimal_load_shift(&self, max_shift: Duration) -> Duration {
+        if self.records.len() < 2 {
+            return Duration::zero();
+        }
+        let interval = self.records[1].time_stamp - self.records[0].time_stamp;
+        if interval <= Duration::zero() {
+            return Duration::zero();
+        }
+        // Nanoseconds are used, as sub-millisecond intervals would truncate to zero milliseconds.
+        // Durations too long to be represented in nanoseconds saturate.
+        let max_shift_in_ns = max_shift
+            .num_nanoseconds()
+            .map_or(i64::MAX, i64::saturating_abs);
+        let interval_in_ns = interval.num_nanoseconds().unwrap_or(i64::MAX);
+        let max_steps = (max_shift_in_ns / interval_in_ns).min(self.records.len() as i64 - 1);
+        let overlap = |steps: i64| -> f64 {
+            self.records
+                .iter()
+                .enumerate()
+                .filter_map(|(index, record)| {
+                    let source = index as i64 - steps;
+                    if source >= 0 && source < self.records.len() as i64 {
+                        let consumption = self.records[source as usize].meter_power_consumption;
+                        Some(record.pv_power_output.min(consumption))
+                    } else {
+                        None
+                    }
+                })
+                .sum()
+        };
+        // Shifts are evaluated by increasing magnitude, so ties resolve to the smallest shift.
+        let mut best_steps = 0;
+        let mut best_overlap = overlap(0);
+        for magnitude in 1..=max_steps {
+            for steps in [-magnitude, magnitude] {
+                let candidate_overlap = overlap(steps);
+                if candidate_overlap > best_overlap {
+                    best_steps = steps;
+                    best_overlap = candidate_overlap;
+                }
+            }
+        }
+        interval * best_steps as i32
+    }
+
+    /// Returns the energy in kWh imported from the grid on each simulated day.
+    fn daily_grid_import_kwh(&self) -> BTreeMap<NaiveDate, f64> {
+        self.reduce_records(
+            BTreeMap::new,
+            |mut daily_import, record, interval_in_h| {
+                *daily_import
+                    .entry(record.time_stamp.date_naive())
+                    .or_insert(0.0) += record.grid_import_w() * interval_in_h / 1000.0;
+                daily_import
+            },
+            |mut a, b| {
+                for (date, import) in b {
+                    *a.entry(date).or_insert(0.0) += import;
+                }
+                a
+            },
+        )
+    }
+}
+
+/// Returns the first `Record` with the peak power output, if any.
+///
+/// # Parameters
+///
+/// * `records` - the `Record`s to search
+fn peak_output(records: &[Record]) -> Option<&Record> {
+    records
+        .iter()
+        .fold(None, |peak: Option<&Record>, record| match peak {
+            Some(peak) if peak.pv_power_output >= record.pv_power_output => Some(peak),
+            _ => Some(record),
+        })
+}
+
+/// Returns the fraction of the produced energy consumed locally instead of being exported.
+/// Returns zero if no energy was produced.
+///
+/// # Parameters
+///
+/// * `produced` - the produced energy
+/// * `exported` - the exported energy in the same unit
+pub(super) fn self_consumption_ratio(produced: f64, exported: f64) -> f64 {
+    if produced > 0.0 {
+        (produced - exported) / produced
+    } else {
+        0.0
+    }
+}
+
+/// Returns the duration in hours with nanosecond precision.
+/// Durations too long to be represented in nanoseconds fall back to millisecond precision.
+///
+/// # Parameters
+///
+/// * `duration` - the duration to convert
+pub(super) fn duration_in_h(duration: Duration) -> f64 {
+    duration.num_nanoseconds().map_or_else(
+        || duration.num_milliseconds() as f64 / 3_600_000.0,
+        |nanoseconds| nanoseconds as f64 / 3_600_000_000_000.0,
+    )
+}
+
+/// Returns the time in hours each `Record` is representative for, which is the interval to the
+/// subsequent `Record`. As the last `Record` has no successor, the preceding interval is assumed.
+///
+/// # Parameters
+///
+/// * `records` - the `Record`s in chronological order
+pub(super) fn record_intervals_in_h(records: &[Record]) -> Vec<f64> {
+    let mut intervals: Vec<f64> = records
+        .windows(2)
+        .map(|pair| duration_in_h(pair[1].time_stamp - pair[0].time_stamp))
+        .collect();
+    if let Some(last_interval) = intervals.last().copied() {
+        intervals.push(last_interval);
+    } else if !records.is_empty() {
+        // A single record does not represent any time span.
+        intervals.push(0.0);
+    }
+    intervals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::float_compare_non_exact;
+    use super::super::super::meter::BrokerMessage;
+    use super::super::tests::simulator_with_records;
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Creates a `PvSimulator` with `Record`s simulated for 21 June 2020 at the specified stride
+    /// and a constant power consumption of 500 W.
+    ///
+    /// # Parameters
+    ///
+    /// * `stride` - the time between subsequent `Record`s
+    fn simulator_with_simulated_day(stride: Duration) -> PvSimulator {
+        simulate_day(PvSimulator::with_seed("", 42), stride)
+    }
+
+    /// Adds `Record`s simulated for 21 June 2020 at the specified stride and a constant power
+    /// consumption of 500 W to the `PvSimulator`.
+    ///
+    /// # Parameters
+    ///
+    /// * `simulator` - the simulator to use
+    /// * `stride` - the time between subsequent `Record`s
+    fn simulate_day(mut simulator: PvSimulator, stride: Duration) -> PvSimulator {
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        let mut time = start;
+        while time < start + Duration::days(1) {
+            simulator
+                .process_message(BrokerMessage::new(500.0, time).unwrap())
+                .unwrap();
+            time += stride;
+        }
+        simulator
+    }
+
+    #[test]
+    /// Tests if the function `net_zero_crossings` detects the begin and end of a midday
+    /// power surplus.
+    fn test_net_zero_crossings() {
+        let mut powers = vec![(500.0, 0.0); 24];
+        for (hour, power) in powers.iter_mut().enumerate().take(17).skip(9) {
+            power.1 = 100.0 * hour as f64;
+        }
+        let simulator = simulator_with_records(&powers);
+        let start = simulator.records[0].time_stamp;
+        assert_eq!(
+            simulator.net_zero_crossings(),
+            vec![
+                (start + Duration::hours(9), true),
+                (start + Duration::hours(17), false)
+            ]
+        );
+        // No crossings without a surplus or without records.
+        assert!(simulator_with_records(&[(500.0, 0.0); 24])
+            .net_zero_crossings()
+            .is_empty());
+        assert!(simulator_with_records(&[]).net_zero_crossings().is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `window` returns exactly the `Record`s within the inclusive bounds.
+    fn test_window() {
+        let simulator = simulator_with_records(&[(500.0, 1000.0); 24]);
+        let start = simulator.records[0].time_stamp;
+        let window = simulator.window(start + Duration::hours(9), start + Duration::hours(17));
+        assert_eq!(window.len(), 9);
+        assert_eq!(window[0].time_stamp, start + Duration::hours(9));
+        assert_eq!(window[8].time_stamp, start + Duration::hours(17));
+        // Bounds between records.
+        let window = simulator.window(
+            start + Duration::minutes(30),
+            start + Duration::minutes(150),
+        );
+        assert_eq!(window.len(), 2);
+        // Empty windows.
+        assert!(simulator
+            .window(start + Duration::hours(2), start + Duration::hours(1))
+            .is_empty());
+        assert!(simulator
+            .window(start - Duration::hours(2), start - Duration::hours(1))
+            .is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `distinct_dates` returns each date once in order.
+    fn test_distinct_dates() {
+        let simulator = simulator_with_records(&[(0.0, 0.0); 60]);
+        assert_eq!(
+            simulator.distinct_dates(),
+            vec![
+                NaiveDate::from_ymd_opt(2020, 6, 21).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 6, 22).unwrap(),
+                NaiveDate::from_ymd_opt(2020, 6, 23).unwrap()
+            ]
+        );
+        assert!(simulator_with_records(&[]).distinct_dates().is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `energy_summary` integrates the power values.
+    fn test_energy_summary() {
+        let simulator = simulator_with_records(&[(1000.0, 0.0), (500.0, 3000.0), (500.0, 0.0)]);
+        let summary = simulator.energy_summary();
+        assert!(float_compare_non_exact(summary.consumed_kwh, 2.0));
+        assert!(float_compare_non_exact(summary.produced_kwh, 3.0));
+        assert!(float_compare_non_exact(summary.net_kwh, 1.0));
+        assert!(float_compare_non_exact(summary.grid_import_kwh, 1.5));
+        assert!(float_compare_non_exact(summary.grid_export_kwh, 2.5));
+        assert!(float_compare_non_exact(
+            summary.grid_export_kwh - summary.grid_import_kwh,
+            summary.net_kwh
+        ));
+        let summary = simulator_with_records(&[]).energy_summary();
+        assert_eq!(summary.consumed_kwh, 0.0);
+        assert_eq!(summary.produced_kwh, 0.0);
+    }
+
+    #[test]
+    /// Tests if the total energies are integrated by the trapezoidal rule over the actual
+    /// time stamps of the `Record`s.
+    fn test_total_energy_kwh() {
+        // A constant output of 3600 W over exactly one hour.
+        let simulator = simulator_with_records(&[(0.0, 3600.0), (0.0, 3600.0)]);
+        assert!(float_compare_non_exact(
+            simulator.total_generated_kwh(),
+            3.6
+        ));
+        assert_eq!(simulator.total_consumed_kwh(), 0.0);
+        assert!(float_compare_non_exact(simulator.net_energy_kwh(), 3.6));
+        // Irregular intervals of one and two hours.
+        let mut simulator = simulator_with_records(&[(1000.0, 0.0), (2000.0, 2000.0)]);
+        let mut last = simulator.records[1];
+        last.time_stamp += Duration::hours(2);
+        last.meter_power_consumption = 0.0;
+        last.pv_power_output = 0.0;
+        simulator.records.push(last);
+        assert!(float_compare_non_exact(simulator.total_consumed_kwh(), 3.5));
+        assert!(float_compare_non_exact(
+            simulator.total_generated_kwh(),
+            3.0
+        ));
+        assert!(float_compare_non_exact(simulator.net_energy_kwh(), -0.5));
+        // Fewer than two records do not span any time.
+        assert_eq!(
+            simulator_with_records(&[(0.0, 3600.0)]).total_generated_kwh(),
+            0.0
+        );
+        assert_eq!(simulator_with_records(&[]).net_energy_kwh(), 0.0);
+    }
+
+    #[test]
+    /// Tests if `energy_summary` integrates by left rectangles including the last `Record`,
+    /// while `total_generated_kwh` integrates by the trapezoidal rule.
+    fn test_energy_integration_rules() {
+        let simulator = simulator_with_records(&[(0.0, 0.0), (0.0, 3000.0), (0.0, 3000.0)]);
+        // Left rectangles: 0 + 3 kWh + 3 kWh for the last record repeating the last hour.
+        let left_rectangles = simulator.energy_summary().produced_kwh;
+        assert!(float_compare_non_exact(left_rectangles, 6.0));
+        assert_eq!(simulator.hourly_yield_wh().iter().sum::<f64>(), 6000.0);
+        // Trapezoids: 1.5 kWh + 3 kWh between the first and the last record.
+        let trapezoids = simulator.total_generated_kwh();
+        assert!(float_compare_non_exact(trapezoids, 4.5));
+        // Both rules agree if the power values are symmetric.
+        let simulator = simulator_with_records(&[(0.0, 0.0), (0.0, 3000.0), (0.0, 0.0)]);
+        assert!(float_compare_non_exact(
+            simulator.energy_summary().produced_kwh,
+            simulator.total_generated_kwh()
+        ));
+    }
+
+    #[test]
+    /// Tests if the function `summary` reports the statistics of all power values and the
+    /// peak output in the afternoon as predicted by the default model.
+    fn test_summary_power_statistics() {
+        let summary =
+            simulator_with_records(&[(1000.0, 0.0), (500.0, 3000.0), (600.0, 0.0)]).summary();
+        assert_eq!(
+            summary.meter_power_consumption,
+            Some(PowerStatistics {
+                min: 500.0,
+                max: 1000.0,
+                mean: 700.0
+            })
+        );
+        assert_eq!(
+            summary.pv_power_output,
+            Some(PowerStatistics {
+                min: 0.0,
+                max: 3000.0,
+                mean: 1000.0
+            })
+        );
+        assert_eq!(
+            summary.total_power_output,
+            Some(PowerStatistics {
+                min: -1000.0,
+                max: 2500.0,
+                mean: 300.0
+            })
+        );
+        let simulator = simulator_with_simulated_day(Duration::minutes(10));
+        let summary = simulator.summary();
+        assert_eq!(summary.record_count, 144);
+        assert!((12..18).contains(&summary.peak_output_time.unwrap().hour()));
+        assert_eq!(summary.meter_power_consumption.unwrap().mean, 500.0);
+        let pv_power_output = summary.pv_power_output.unwrap();
+        assert!(pv_power_output.min == 0.0 && pv_power_output.max > 3000.0);
+    }
+
+    #[test]
+    /// Tests if the function `summary` combines the metrics of all `Record`s.
+    fn test_summary() {
+        let simulator = simulator_with_records(&[
+            (1000.0, 0.0),
+            (500.0, 3000.0),
+            (500.0, 3000.0),
+            (500.0, 0.0),
+        ]);
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        let summary = simulator.summary();
+        assert!(float_compare_non_exact(summary.total_energy_kwh, 6.0));
+        assert!(float_compare_non_exact(summary.peak_output_w, 3000.0));
+        assert_eq!(summary.peak_output_time, Some(start + Duration::hours(1)));
+        // 1 kWh of the 6 kWh produced is consumed locally.
+        assert!(float_compare_non_exact(
+            summary.self_consumption_ratio,
+            1.0 / 6.0
+        ));
+        assert_eq!(summary.record_count, 4);
+        assert_eq!(summary.start_time, Some(start));
+        assert_eq!(summary.end_time, Some(start + Duration::hours(3)));
+        assert!(serde_json::to_string(&summary).is_ok());
+        let summary = simulator_with_records(&[]).summary();
+        assert_eq!(summary.total_energy_kwh, 0.0);
+        assert_eq!(summary.peak_output_w, 0.0);
+        assert_eq!(summary.peak_output_time, None);
+        assert_eq!(summary.self_consumption_ratio, 0.0);
+        assert_eq!(summary.record_count, 0);
+        assert_eq!(summary.start_time, None);
+        assert_eq!(summary.end_time, None);
+        assert_eq!(summary.meter_power_consumption, None);
+        assert_eq!(summary.pv_power_output, None);
+        assert_eq!(summary.total_power_output, None);
+    }
+
+    #[test]
+    /// Tests if the function `aggregate_sites` sums the power values of misaligned sites.
+    fn test_aggregate_sites() {
+        let site_a = simulator_with_records(&[(100.0, 1000.0), (200.0, 2000.0), (300.0, 0.0)]);
+        let mut site_b = simulator_with_records(&[(10.0, 500.0), (20.0, 700.0)]);
+        // The second site reports a few seconds late.
+        for record in site_b.records.iter_mut() {
+            record.time_stamp += Duration::seconds(3);
+        }
+        let aggregated = aggregate_sites(&[site_a, site_b], Duration::minutes(1));
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        let expected = [(110.0, 1500.0), (220.0, 2700.0), (300.0, 0.0)];
+        assert_eq!(aggregated.len(), expected.len());
+        for (hour, (record, (consumption, output))) in
+            aggregated.iter().zip(expected.iter()).enumerate()
+        {
+            assert_eq!(record.time_stamp, start + Duration::hours(hour as i64));
+            assert!(float_compare_non_exact(
+                record.meter_power_consumption,
+                *consumption
+            ));
+            assert!(float_compare_non_exact(record.pv_power_output, *output));
+            assert!(float_compare_non_exact(
+                record.total_power_output,
+                output - consumption
+            ));
+        }
+        assert!(aggregate_sites(&[], Duration::minutes(1)).is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `yield_sanity_check` detects implausibly low yields.
+    fn test_yield_sanity_check() {
+        let sanity = simulator_with_simulated_day(Duration::minutes(5)).yield_sanity_check();
+        assert_eq!(sanity.status, YieldStatus::Plausible);
+        assert!(sanity.ratio > MINIMUM_PLAUSIBLE_YIELD_RATIO);
+        assert!(sanity.ratio <= 1.0);
+        let mut simulator = PvSimulator::with_seed("", 42);
+        simulator.set_peak_scaling(0.0).unwrap();
+        let sanity = simulate_day(simulator, Duration::minutes(5)).yield_sanity_check();
+        assert_eq!(sanity.status, YieldStatus::ImplausiblyLow);
+        assert_eq!(sanity.ratio, 0.0);
+        // A configured capacity that is not reached is detected as well.
+        let mut simulator = simulator_with_simulated_day(Duration::minutes(5));
+        simulator.set_peak_scaling(1_000_000.0).unwrap();
+        let sanity = simulator.yield_sanity_check();
+        assert_eq!(sanity.status, YieldStatus::ImplausiblyLow);
+        assert!(sanity.ratio > 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `compare_runs` reports the maximum difference to a perturbed run.
+    fn test_compare_runs() {
+        let simulator = simulator_with_simulated_day(Duration::minutes(15));
+        let run = simulator.records.clone();
+        let diff = compare_runs(&run, &run);
+        assert_eq!(diff.max_diff(), 0.0);
+        assert!(diff.is_within(0.0));
+        let mut perturbed = run.clone();
+        perturbed[10].meter_power_consumption += 0.5;
+        perturbed[20].pv_power_output -= 2.0;
+        let diff = compare_runs(&run, &perturbed);
+        assert!(float_compare_non_exact(diff.max_consumption_diff, 0.5));
+        assert!(float_compare_non_exact(diff.max_output_diff, 2.0));
+        assert_eq!(diff.max_total_diff, 0.0);
+        assert!(float_compare_non_exact(diff.max_diff(), 2.0));
+        assert!(diff.is_within(2.5));
+        assert!(!diff.is_within(1.0));
+        // Mismatching time stamps.
+        perturbed[0].time_stamp += Duration::seconds(1);
+        let diff = compare_runs(&run, &perturbed[..perturbed.len() - 2]);
+        assert_eq!(diff.mismatched_time_stamps, 3);
+        assert!(!diff.is_within(10.0));
+    }
+
+    #[test]
+    /// Tests if the function `decimate` retains every n-th record including the first one.
+    fn test_decimate() {
+        let powers: Vec<(f64, f64)> = (0..7).map(|i| (i as f64, 0.0)).collect();
+        let simulator = simulator_with_records(&powers);
+        assert_eq!(simulator.decimate(1), simulator.records);
+        let decimated = simulator.decimate(2);
+        assert_eq!(decimated.len(), 4);
+        assert_eq!(decimated[0], simulator.records[0]);
+        assert_eq!(decimated[3], simulator.records[6]);
+        assert_eq!(simulator.decimate(10), vec![simulator.records[0]]);
+        assert!(PvSimulator::new("").decimate(3).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests if the function `decimate` panics for a decimation factor of zero.
+    fn test_decimate_zero() {
+        simulator_with_records(&[(500.0, 0.0)]).decimate(0);
+    }
+
+    #[test]
+    /// Tests if the function `apply` of the `Aggregation` enum combines values correctly.
+    fn test_aggregation_apply() {
+        let values = [2.0, 5.0, 1.0, 4.0];
+        assert_eq!(Aggregation::Mean.apply(values.iter().copied()), Some(3.0));
+        assert_eq!(Aggregation::Max.apply(values.iter().copied()), Some(5.0));
+        assert_eq!(Aggregation::Min.apply(values.iter().copied()), Some(1.0));
+        assert_eq!(Aggregation::Sum.apply(values.iter().copied()), Some(12.0));
+        assert_eq!(Aggregation::Last.apply(values.iter().copied()), Some(4.0));
+        assert_eq!(Aggregation::Mean.apply(std::iter::empty()), None);
+    }
+
+    #[test]
+    /// Tests if the function `resample` with `Max` aggregation retains the peak value
+    /// of each bucket.
+    fn test_resample_max() {
+        let powers: Vec<(f64, f64)> = (0..24)
+            .map(|hour| (((hour * 7) % 24) as f64, (hour * 100) as f64))
+            .collect();
+        let simulator = simulator_with_records(&powers);
+        let resampled = simulator.resample(Duration::hours(6), Aggregation::Max);
+        assert_eq!(resampled.len(), 4);
+        for (bucket, record) in resampled.iter().enumerate() {
+            let bucket_records = &simulator.records[bucket * 6..(bucket + 1) * 6];
+            assert_eq!(record.time_stamp, bucket_records[0].time_stamp);
+            assert_eq!(
+                record.meter_power_consumption,
+                bucket_records
+                    .iter()
+                    .map(|record| record.meter_power_consumption)
+                    .fold(f64::MIN, f64::max)
+            );
+            assert_eq!(record.pv_power_output, bucket_records[5].pv_power_output);
+            assert_eq!(record.phase_consumption(), None);
+        }
+        // Buckets are aligned and empty buckets are omitted.
+        let sparse = simulator_with_records(&[(1.0, 0.0), (2.0, 0.0), (3.0, 0.0)]);
+        let sparse = PvSimulator {
+            records: vec![sparse.records[0], sparse.records[2]],
+            ..sparse
+        };
+        let resampled = sparse.resample(Duration::hours(1), Aggregation::Mean);
+        assert_eq!(resampled, sparse.records);
+    }
+
+    #[test]
+    /// Tests if the function `hourly_yield_wh` attributes the energy to the correct hours.
+    fn test_hourly_yield_wh() {
+        let simulator = simulator_with_simulated_day(Duration::minutes(5));
+        let hourly_yield = simulator.hourly_yield_wh();
+        let (peak_hour, _) = hourly_yield
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        assert!((12..18).contains(&peak_hour));
+        // No energy is produced at night.
+        for hour in (0..5).chain(21..24) {
+            assert_eq!(hourly_yield[hour], 0.0);
+        }
+        // The total matches the integral of the scaled Kumaraswamy PDF over 16 hours.
+        let total: f64 = hourly_yield.iter().sum();
+        assert!((total / (1650.0 * 16.0) - 1.0).abs() < 0.05);
+        // Constant output is distributed evenly.
+        let simulator = simulator_with_records(&[(0.0, 1000.0); 48]);
+        assert_eq!(simulator.hourly_yield_wh(), [2000.0; 24]);
+        assert_eq!(simulator_with_records(&[]).hourly_yield_wh(), [0.0; 24]);
+    }
+
+    #[test]
+    /// Tests if the function `specific_yield` normalises the produced energy by the
+    /// installed capacity.
+    fn test_specific_yield() {
+        // 4 kWh produced.
+        let mut simulator = simulator_with_records(&[(0.0, 1000.0), (0.0, 3000.0), (0.0, 0.0)]);
+        simulator.set_peak_scaling(2000.0).unwrap();
+        assert!(float_compare_non_exact(simulator.specific_yield(), 2.0));
+        simulator.set_peak_scaling(0.0).unwrap();
+        assert_eq!(simulator.specific_yield(), 0.0);
+        assert!(simulator.set_peak_scaling(-1.0).is_err());
+        assert!(simulator.set_peak_scaling(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    /// Tests if the function `ramp_rates` computes the change of power output per second.
+    fn test_ramp_rates() {
+        let simulator =
+            simulator_with_records(&[(0.0, 0.0), (0.0, 3600.0), (0.0, 3600.0), (0.0, 0.0)]);
+        let ramp_rates = simulator.ramp_rates();
+        assert_eq!(ramp_rates.len(), 3);
+        assert_eq!(ramp_rates[0].0, simulator.records[1].time_stamp);
+        assert!(float_compare_non_exact(ramp_rates[0].1, 1.0));
+        assert!(float_compare_non_exact(ramp_rates[1].1, 0.0));
+        assert!(float_compare_non_exact(ramp_rates[2].1, -1.0));
+        assert!(simulator_with_records(&[(0.0, 0.0)])
+            .ramp_rates()
+            .is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `peak_net_magnitude` returns the largest import or export.
+    fn test_peak_net_magnitude() {
+        // The export peak exceeds the import peak.
+        let simulator = simulator_with_records(&[(800.0, 0.0), (500.0, 3000.0), (0.0, 1000.0)]);
+        assert_eq!(simulator.peak_net_magnitude(), 2500.0);
+        // The import peak exceeds the export peak.
+        let simulator = simulator_with_records(&[(4000.0, 0.0), (500.0, 3000.0)]);
+        assert_eq!(simulator.peak_net_magnitude(), 4000.0);
+        assert_eq!(simulator_with_records(&[]).peak_net_magnitude(), 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `deviation_from_baseline` highlights a load spike.
+    fn test_deviation_from_baseline() {
+        let mut powers = vec![(500.0, 0.0); 12];
+        powers[8].0 = 3000.0;
+        let simulator = simulator_with_records(&powers);
+        let start = simulator.records[0].time_stamp;
+        let deviations = simulator.deviation_from_baseline(Duration::hours(3));
+        // The first three hours lack a fully populated baseline.
+        assert_eq!(deviations.len(), 9);
+        assert_eq!(deviations[0].0, start + Duration::hours(3));
+        for (time, deviation) in &deviations {
+            let hours = (*time - start).num_hours();
+            if hours == 8 {
+                assert!(float_compare_non_exact(*deviation, 2500.0));
+            } else if hours > 8 && hours <= 11 {
+                // The spike raises the baseline of the subsequent records.
+                assert!(*deviation < 0.0);
+            } else {
+                assert!(float_compare_non_exact(*deviation, 0.0));
+            }
+        }
+        assert!(simulator
+            .deviation_from_baseline(Duration::zero())
+            .is_empty());
+        assert!(simulator_with_records(&[])
+            .deviation_from_baseline(Duration::hours(1))
+            .is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `production_cv` increases with the noise of the power output.
+    fn test_production_cv() {
+        let calm = simulate_day(PvSimulator::with_seed("", 1), Duration::minutes(5));
+        let mut noisy = PvSimulator::with_seed("", 1);
+        noisy.set_jitter_amplitude(0.5).unwrap();
+        let noisy = simulate_day(noisy, Duration::minutes(5));
+        assert!(calm.production_cv() > 0.0);
+        assert!(noisy.production_cv() > calm.production_cv());
+        // Night time records are ignored.
+        let simulator = simulator_with_records(&[(0.0, 0.0), (0.0, 1000.0), (0.0, 1000.0)]);
+        assert_eq!(simulator.production_cv(), 0.0);
+        let simulator = simulator_with_records(&[(0.0, 0.0), (0.0, 1000.0), (0.0, 3000.0)]);
+        assert!(float_compare_non_exact(
+            simulator.production_cv(),
+            2.0_f64.sqrt() / 2.0
+        ));
+        // Degenerate record sets.
+        assert_eq!(
+            simulator_with_records(&[(0.0, 1000.0)]).production_cv(),
+            0.0
+        );
+        assert_eq!(simulator_with_records(&[]).production_cv(), 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `average_daily_grid_import_kwh` averages the grid import
+    /// of each day.
+    fn test_average_daily_grid_import_kwh() {
+        // 24 kWh import on the first day.
+        let mut powers = vec![(1000.0, 0.0); 24];
+        // 6 kWh import on the second day, while the surplus is not accounted for.
+        powers.extend(vec![(500.0, 0.0); 12]);
+        powers.extend(vec![(500.0, 1000.0); 12]);
+        let simulator = simulator_with_records(&powers);
+        let daily_import: Vec<f64> = simulator
+            .daily_grid_import_kwh()
+            .values()
+            .copied()
+            .collect();
+        assert_eq!(daily_import.len(), 2);
+        assert!(float_compare_non_exact(daily_import[0], 24.0));
+        assert!(float_compare_non_exact(daily_import[1], 6.0));
+        assert!(float_compare_non_exact(
+            simulator.average_daily_grid_import_kwh(),
+            15.0
+        ));
+        assert_eq!(
+            simulator_with_records(&[]).average_daily_grid_import_kwh(),
+            0.0
+        );
+    }
+
+    #[test]
+    /// Tests if the function `producing_time_fraction` approximates the fraction of
+    /// daylight hours.
+    fn test_producing_time_fraction() {
+        let simulator = simulator_with_simulated_day(Duration::minutes(5));
+        // The sun is out from 5:00 to 21:00.
+        assert!((simulator.producing_time_fraction() - 16.0 / 24.0).abs() < 0.01);
+        let simulator =
+            simulator_with_records(&[(0.0, 0.0), (0.0, 1000.0), (0.0, 0.0), (0.0, 5.0)]);
+        assert!(float_compare_non_exact(
+            simulator.producing_time_fraction(),
+            0.5
+        ));
+        assert_eq!(simulator_with_records(&[]).producing_time_fraction(), 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `time_weighted_average_output` weights the output of each
+    /// `Record` by its interval.
+    fn test_time_weighted_average_output() {
+        let mut simulator = simulator_with_records(&[(0.0, 1000.0), (0.0, 0.0), (0.0, 0.0)]);
+        // The first record persists for 2 hours, the second for 1 hour.
+        simulator.records[1].time_stamp += Duration::hours(1);
+        simulator.records[2].time_stamp += Duration::hours(1);
+        let naive_mean = 1000.0 / 3.0;
+        let weighted = simulator.time_weighted_average_output();
+        assert!(float_compare_non_exact(weighted, 2000.0 / 4.0));
+        assert!(!float_compare_non_exact(weighted, naive_mean));
+        // Uniform time stamps result in the arithmetic mean.
+        let simulator = simulator_with_records(&[(0.0, 1000.0), (0.0, 0.0), (0.0, 500.0)]);
+        assert!(float_compare_non_exact(
+            simulator.time_weighted_average_output(),
+            500.0
+        ));
+        assert!(float_compare_non_exact(
+            simulator_with_records(&[(0.0, 800.0)]).time_weighted_average_output(),
+            800.0
+        ));
+        assert_eq!(
+            simulator_with_records(&[]).time_weighted_average_output(),
+            0.0
+        );
+    }
+
+    #[test]
+    /// Tests if the function `cumulative_energy_wh` resets at midnight and ends each day
+    /// with its total production.
+    fn test_cumulative_energy_wh() {
+        let mut simulator = simulator_with_simulated_day(Duration::minutes(15));
+        let first_day_total = simulator.energy_summary().produced_kwh * 1000.0;
+        let first_day = simulator.records.clone();
+        simulator
+            .records
+            .extend(first_day.iter().map(|record| Record {
+                time_stamp: record.time_stamp + Duration::days(1),
+                pv_power_output: record.pv_power_output * 2.0,
+                ..*record
+            }));
+        let cumulative = simulator.cumulative_energy_wh();
+        assert_eq!(cumulative.len(), simulator.records.len());
+        let midnight = first_day.len();
+        assert_eq!(cumulative[0].1, 0.0);
+        assert_eq!(cumulative[midnight].1, 0.0);
+        assert_eq!(
+            cumulative[midnight].0,
+            Utc.with_ymd_and_hms(2020, 6, 22, 0, 0, 0).unwrap()
+        );
+        assert!(float_compare_non_exact(
+            cumulative[midnight - 1].1,
+            first_day_total
+        ));
+        assert!(float_compare_non_exact(
+            cumulative.last().unwrap().1,
+            2.0 * first_day_total
+        ));
+        // The running total never decreases within a day.
+        for pair in cumulative[..midnight].windows(2) {
+            assert!(pair[1].1 >= pair[0].1);
+        }
+        assert!(simulator_with_records(&[])
+            .cumulative_energy_wh()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    /// Tests if parallel and serial analyses produce identical results.
+    fn test_parallelism() {
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        let mut simulator = PvSimulator::with_seed("", 7);
+        for i in 0..50_000 {
+            simulator
+                .process_message(
+                    BrokerMessage::new((i % 97) as f64 * 31.7, start + Duration::seconds(13 * i))
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+        let serial_summary = simulator.energy_summary();
+        let serial_hourly_yield = simulator.hourly_yield_wh();
+        let serial_daily_import = simulator.daily_grid_import_kwh();
+        assert!(simulator.set_parallelism(Some(0)).is_err());
+        simulator.set_parallelism(Some(4)).unwrap();
+        assert_eq!(simulator.energy_summary(), serial_summary);
+        assert_eq!(simulator.hourly_yield_wh(), serial_hourly_yield);
+        assert_eq!(simulator.daily_grid_import_kwh(), serial_daily_import);
+        simulator.set_parallelism(None).unwrap();
+        assert_eq!(simulator.energy_summary(), serial_summary);
+    }
+
+    #[test]
+    /// Tests if the function `load_match_score` rates the match of the power output with
+    /// a target load profile.
+    fn test_load_match_score() {
+        let simulator = simulator_with_simulated_day(Duration::minutes(15));
+        let production: Vec<f64> = simulator
+            .records
+            .iter()
+            .map(|record| record.pv_power_output)
+            .collect();
+        assert_eq!(simulator.load_match_score(&production), 1.0);
+        let shifted: Vec<f64> = production
+            .iter()
+            .cycle()
+            .skip(8)
+            .take(production.len())
+            .copied()
+            .collect();
+        let score = simulator.load_match_score(&shifted);
+        assert!(score < 1.0);
+        let flat = vec![production.iter().sum::<f64>() / production.len() as f64; production.len()];
+        assert!(simulator.load_match_score(&flat) < 1.0);
+        // Only records with a target load are compared.
+        assert_eq!(simulator.load_match_score(&production[..10]), 1.0);
+        let simulator = simulator_with_records(&[(0.0, 100.0), (0.0, 300.0)]);
+        // RMSE of 100 W at a mean target of 200 W.
+        assert!(float_compare_non_exact(
+            simulator.load_match_score(&[200.0, 200.0]),
+            0.5
+        ));
+        assert_eq!(simulator.load_match_score(&[]), 0.0);
+        assert_eq!(simulator.load_match_score(&[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `crosses_dst` detects daylight saving time transitions.
+    fn test_crosses_dst() {
+        // Central European Summer Time started on 29 March 2020 at 01:00 UTC.
+        let mut simulator = simulator_with_records(&[(0.0, 0.0); 6]);
+        let start = Utc.with_ymd_and_hms(2020, 3, 28, 22, 0, 0).unwrap();
+        for (i, record) in simulator.records.iter_mut().enumerate() {
+            record.time_stamp = start + Duration::hours(i as i64);
+        }
+        assert!(!simulator.crosses_dst());
+        simulator.set_timezone(Some(chrono_tz::Europe::Berlin));
+        assert!(simulator.crosses_dst());
+        simulator.set_timezone(Some(chrono_tz::Asia::Tokyo));
+        assert!(!simulator.crosses_dst());
+        let mut simulator = simulator_with_records(&[(0.0, 0.0); 24]);
+        simulator.set_timezone(Some(chrono_tz::Europe::Berlin));
+        assert!(!simulator.crosses_dst());
+    }
+
+    #[test]
+    /// Tests if the function `daily_summaries` summarises each day separately.
+    fn test_daily_summaries() {
+        let mut powers = vec![(1000.0, 0.0); 12];
+        powers.extend(vec![(500.0, 3000.0); 12]);
+        powers.extend(vec![(500.0, 0.0); 23]);
+        powers.push((500.0, 2000.0));
+        let simulator = simulator_with_records(&powers);
+        let summaries = simulator.daily_summaries();
+        assert_eq!(summaries.len(), 2);
+        let first = summaries[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2020, 6, 21).unwrap());
+        assert!(float_compare_non_exact(first.consumed_kwh, 18.0));
+        assert!(float_compare_non_exact(first.produced_kwh, 36.0));
+        assert_eq!(first.peak_output_w, 3000.0);
+        assert_eq!(
+            first.peak_output_time,
+            Utc.with_ymd_and_hms(2020, 6, 21, 12, 0, 0).unwrap()
+        );
+        // 6 kWh of the 36 kWh produced are consumed locally.
+        assert!(float_compare_non_exact(
+            first.self_consumption_ratio,
+            6.0 / 36.0
+        ));
+        let second = summaries[1];
+        assert_eq!(second.date, NaiveDate::from_ymd_opt(2020, 6, 22).unwrap());
+        assert!(float_compare_non_exact(second.consumed_kwh, 12.0));
+        assert!(float_compare_non_exact(second.produced_kwh, 2.0));
+        assert_eq!(
+            second.peak_output_time,
+            Utc.with_ymd_and_hms(2020, 6, 22, 23, 0, 0).unwrap()
+        );
+        assert!(simulator_with_records(&[]).daily_summaries().is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `optimal_load_shift` moves an evening consumption peak towards
+    /// the midday output peak.
+    fn test_optimal_load_shift() {
+        let powers: Vec<(f64, f64)> = (0..24)
+            .map(|hour: i32| {
+                let consumption = if (18..=20).contains(&hour) {
+                    2000.0
+                } else {
+                    100.0
+                };
+                let output = (3000.0 - 1000.0 * f64::from((hour - 12).abs())).max(0.0);
+                (consumption, output)
+            })
+            .collect();
+        let simulator = simulator_with_records(&powers);
+        assert_eq!(
+            simulator.optimal_load_shift(Duration::hours(12)),
+            Duration::hours(-7)
+        );
+        // The shift is limited to the maximum shift.
+        assert_eq!(
+            simulator.optimal_load_shift(Duration::hours(5)),
+            Duration::hours(-5)
+        );
+        assert_eq!(
+            simulator.optimal_load_shift(Duration::zero()),
+            Duration::zero()
+        );
+        assert_eq!(
+            simulator_with_records(&[(100.0, 100.0)]).optimal_load_shift(Duration::hours(1)),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    /// Tests if `Record`s less than a millisecond apart are handled without dividing by zero.
+    fn test_sub_millisecond_records() {
+        let mut simulator = simulator_with_records(&[(0.0, 100.0), (100.0, 0.0), (0.0, 0.0)]);
+        let start = simulator.records[0].time_stamp;
+        for (i, record) in simulator.records.iter_mut().enumerate() {
+            record.time_stamp = start + Duration::microseconds(100 * i as i64);
+        }
+        assert_eq!(
+            simulator.optimal_load_shift(Duration::milliseconds(1)),
+            Duration::microseconds(-100)
+        );
+        let intervals = record_intervals_in_h(&simulator.records);
+        assert_eq!(intervals.len(), 3);
+        for interval in intervals {
+            assert!((interval - 1e-4 / 3600.0).abs() < 1e-15);
+        }
+        assert_eq!(simulator.ramp_rates().len(), 2);
+    }
+}
@@ -0,0 +1,147 @@
+//! The `archive` module allows export of a simulation run including its configuration
+//! into a single HDF5 file for scientific archival.
+
+use super::super::pv_error::PvError;
+use super::PvSimulator;
+use hdf5::{File, H5Type};
+use std::path::Path;
+
+/// The name of the dataset containing the time stamps in milliseconds since the UNIX epoch.
+const TIME_STAMP_DATASET: &str = "time_stamp_ms";
+/// The name of the dataset containing the power consumption in watt.
+const CONSUMPTION_DATASET: &str = "meter_power_consumption";
+/// The name of the dataset containing the AC power output in watt.
+const OUTPUT_DATASET: &str = "pv_power_output";
+/// The name of the dataset containing the total power output in watt.
+const TOTAL_DATASET: &str = "total_power_output";
+
+impl PvSimulator {
+    /// Writes the `Record`s and the configuration to the specified HDF5 file.
+    /// The time stamps and power values of the `Record`s are stored as one-dimensional
+    /// datasets, while the configuration is stored as attributes of the root group.
+    /// Optional parameters are only stored if specified.
+    /// Fails if the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the HDF5 file
+    pub fn write_hdf5<P: AsRef<Path>>(&self, path: P) -> Result<(), PvError> {
+        let file = File::create(path).map_err(hdf5_error)?;
+        let time_stamps: Vec<i64> = self
+            .records
+            .iter()
+            .map(|record| record.time_stamp.timestamp_millis())
+            .collect();
+        write_dataset(&file, TIME_STAMP_DATASET, &time_stamps)?;
+        let consumption: Vec<f64> = self
+            .records
+            .iter()
+            .map(|record| record.meter_power_consumption)
+            .collect();
+        write_dataset(&file, CONSUMPTION_DATASET, &consumption)?;
+        let output: Vec<f64> = self
+            .records
+            .iter()
+            .map(|record| record.pv_power_output)
+            .collect();
+        write_dataset(&file, OUTPUT_DATASET, &output)?;
+        let total: Vec<f64> = self
+            .records
+            .iter()
+            .map(|record| record.total_power_output)
+            .collect();
+        write_dataset(&file, TOTAL_DATASET, &total)?;
+        let config = self.manifest().config;
+        let attributes = [
+            ("inverter_efficiency", Some(config.inverter_efficiency)),
+            ("inverter_limit_w", config.inverter_limit_w),
+            ("jitter_amplitude", Some(config.jitter_amplitude)),
+            ("deadband_w", config.deadband_w),
+            ("export_limit_w", config.export_limit_w),
+            ("peak_scaling", config.peak_scaling),
+            ("battery_capacity_wh", config.battery_capacity_wh),
+            ("albedo", Some(config.albedo)),
+        ];
+        for (name, value) in attributes {
+            if let Some(value) = value {
+                file.new_attr::<f64>()
+                    .create(name)
+                    .and_then(|attribute| attribute.write_scalar(&value))
+                    .map_err(hdf5_error)?;
+            }
+        }
+        file.close().map_err(hdf5_error)
+    }
+}
+
+/// Writes the values to a new one-dimensional dataset of the HDF5 file.
+/// Fails if the dataset cannot be written.
+///
+/// # Parameters
+///
+/// * `file` - the HDF5 file
+/// * `name` - the name of the dataset
+/// * `values` - the values of the dataset
+fn write_dataset<T: H5Type>(file: &File, name: &str, values: &[T]) -> Result<(), PvError> {
+    file.new_dataset::<T>()
+        .shape(values.len())
+        .create(name)
+        .and_then(|dataset| dataset.write_raw(values))
+        .map_err(hdf5_error)
+}
+
+/// Converts an HDF5 error to a `PvError`.
+///
+/// # Parameters
+///
+/// * `error` - the error to convert
+fn hdf5_error(error: hdf5::Error) -> PvError {
+    PvError::InternalError(format!("The HDF5 file could not be written: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Record;
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::fs::remove_file;
+
+    #[test]
+    /// Tests if the function `write_hdf5` writes the power values and configuration, which
+    /// can be read back.
+    fn test_write_hdf5() {
+        let mut simulator = PvSimulator::new("");
+        simulator.set_peak_scaling(2000.0).unwrap();
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        simulator.records = (0..5)
+            .map(|i| Record::new(start + Duration::seconds(5 * i), 100.0 * i as f64, 50.5))
+            .collect();
+        let path = std::env::temp_dir().join("pv_simulator_test_records.h5");
+        simulator.write_hdf5(&path).unwrap();
+        let file = File::open(&path).unwrap();
+        let time_stamps: Vec<i64> = file
+            .dataset(TIME_STAMP_DATASET)
+            .unwrap()
+            .read_raw()
+            .unwrap();
+        let consumption: Vec<f64> = file
+            .dataset(CONSUMPTION_DATASET)
+            .unwrap()
+            .read_raw()
+            .unwrap();
+        let output: Vec<f64> = file.dataset(OUTPUT_DATASET).unwrap().read_raw().unwrap();
+        let total: Vec<f64> = file.dataset(TOTAL_DATASET).unwrap().read_raw().unwrap();
+        for (i, record) in simulator.records.iter().enumerate() {
+            assert_eq!(time_stamps[i], record.time_stamp.timestamp_millis());
+            assert_eq!(consumption[i], record.meter_power_consumption);
+            assert_eq!(output[i], record.pv_power_output);
+            assert_eq!(total[i], record.total_power_output);
+        }
+        let peak_scaling: f64 = file.attr("peak_scaling").unwrap().read_scalar().unwrap();
+        assert_eq!(peak_scaling, 2000.0);
+        // Unspecified parameters are omitted.
+        assert!(file.attr("inverter_limit_w").is_err());
+        file.close().unwrap();
+        remove_file(path).expect("The test HDF5 file could not be removed.");
+    }
+}
@@ -0,0 +1,354 @@
+//! The `battery` module allows simulation of a battery storing surplus energy produced by
+//! the photovoltaic component for later consumption.
+
+use super::super::pv_error::PvError;
+use super::analysis::{record_intervals_in_h, self_consumption_ratio};
+use super::{PvSimulator, Record};
+use chrono::{DateTime, Duration, Utc};
+
+/// A `Battery` that is charged by surplus power output and discharged to cover power
+/// consumption exceeding the power output.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Battery {
+    capacity_wh: f64,
+    max_power_w: Option<f64>,
+    initial_charge_wh: f64,
+}
+
+impl Battery {
+    /// Creates a new empty `Battery` without power limit.
+    /// Fails if the capacity is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacity_wh` - the usable capacity in Wh
+    pub fn new(capacity_wh: f64) -> Result<Self, PvError> {
+        validate_positive_finite(capacity_wh)?;
+        Ok(Battery {
+            capacity_wh,
+            max_power_w: None,
+            initial_charge_wh: 0.0,
+        })
+    }
+
+    /// Sets the maximum power in watt the `Battery` can be charged or discharged with.
+    /// Per default the power is not limited.
+    /// Fails if the limit is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `max_power_w` - the maximum charging and discharging power, if any
+    pub fn set_max_power(&mut self, max_power_w: Option<f64>) -> Result<(), PvError> {
+        if let Some(max_power) = max_power_w {
+            validate_positive_finite(max_power)?;
+        }
+        self.max_power_w = max_power_w;
+        Ok(())
+    }
+
+    /// Sets the energy stored in the `Battery` at the start of the simulation.
+    /// Per default the `Battery` is empty.
+    /// Fails if the charge is not within [0, capacity].
+    ///
+    /// # Parameters
+    ///
+    /// * `initial_charge_wh` - the initial state of charge in Wh
+    pub fn set_initial_charge(&mut self, initial_charge_wh: f64) -> Result<(), PvError> {
+        if (0.0..=self.capacity_wh).contains(&initial_charge_wh) {
+            self.initial_charge_wh = initial_charge_wh;
+            Ok(())
+        } else {
+            Err(PvError::InternalError(format!(
+                "{} is not a valid charge for a battery with a capacity of {} Wh.",
+                initial_charge_wh, self.capacity_wh
+            )))
+        }
+    }
+
+    /// Returns the usable capacity in Wh.
+    pub fn capacity_wh(&self) -> f64 {
+        self.capacity_wh
+    }
+}
+
+/// The `BatteryState` during the interval a `Record` is representative for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) struct BatteryState {
+    /// The state of charge in Wh at the time stamp of the `Record`.
+    pub soc_wh: f64,
+    /// The energy in Wh charged into the `Battery` during the interval, which is negative
+    /// while discharging.
+    pub charged_wh: f64,
+}
+
+/// Simulates the greedy dispatch of the `Battery` over the `Record`s: Surplus power output
+/// charges the `Battery` and power consumption exceeding the power output discharges it
+/// as far as its capacity and power limit allow.
+///
+/// # Parameters
+///
+/// * `records` - the `Record`s in chronological order
+/// * `battery` - the battery to dispatch
+pub(super) fn dispatch_battery(records: &[Record], battery: &Battery) -> Vec<BatteryState> {
+    let mut soc_wh = battery.initial_charge_wh;
+    records
+        .iter()
+        .zip(record_intervals_in_h(records))
+        .map(|(record, interval_in_h)| {
+            let max_energy_wh = battery
+                .max_power_w
+                .map_or(f64::INFINITY, |max_power| max_power * interval_in_h);
+            let net_energy_wh = record.net_load() * interval_in_h;
+            let charged_wh = if net_energy_wh > 0.0 {
+                -net_energy_wh.min(soc_wh).min(max_energy_wh)
+            } else {
+                (-net_energy_wh)
+                    .min(battery.capacity_wh - soc_wh)
+                    .min(max_energy_wh)
+            };
+            let state = BatteryState { soc_wh, charged_wh };
+            soc_wh += charged_wh;
+            state
+        })
+        .collect()
+}
+
+/// Checks if the specified value is a positive finite number.
+///
+/// # Parameters
+///
+/// * `value` - the value to check
+fn validate_positive_finite(value: f64) -> Result<(), PvError> {
+    if value.is_finite() && value.is_sign_positive() {
+        Ok(())
+    } else {
+        Err(PvError::InternalError(format!(
+            "{} is not a positive finite number.",
+            value
+        )))
+    }
+}
+
+impl PvSimulator {
+    /// Returns the state of charge in Wh of the modelled `Battery` at the time stamp
+    /// of each `Record`. Returns `None` if no `Battery` is modelled.
+    pub fn battery_soc_wh(&self) -> Option<Vec<(DateTime<Utc>, f64)>> {
+        self.battery.as_ref().map(|battery| {
+            self.records
+                .iter()
+                .zip(dispatch_battery(&self.records, battery))
+                .map(|(record, state)| (record.time_stamp, state.soc_wh))
+                .collect()
+        })
+    }
+
+    /// Returns the number of full charge-equivalent cycles of the modelled `Battery`, which is
+    /// the total discharged energy divided by its capacity.
+    /// Returns zero if no `Battery` is modelled.
+    pub fn battery_cycle_count(&self) -> f64 {
+        self.battery.as_ref().map_or(0.0, |battery| {
+            let discharged_wh: f64 = dispatch_battery(&self.records, battery)
+                .iter()
+                .map(|state| (-state.charged_wh).max(0.0))
+                .sum();
+            discharged_wh / battery.capacity_wh
+        })
+    }
+
+    /// Simulates the dispatch of an initially empty `Battery` of each candidate capacity over
+    /// the `Record`s and returns the capacity in kWh and the resulting self-consumption ratio,
+    /// which is the fraction of the produced energy consumed locally, either directly or
+    /// via the `Battery`. Energy remaining in the `Battery` at the end of the simulation does
+    /// not count as consumed. If a `Battery` is modelled, its power
+    /// limit applies to all candidates.
+    /// Fails if any capacity is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacities_kwh` - the candidate capacities in kWh
+    pub fn battery_sizing_sweep(&self, capacities_kwh: &[f64]) -> Result<Vec<(f64, f64)>, PvError> {
+        let intervals = record_intervals_in_h(&self.records);
+        let produced_wh: f64 = self
+            .records
+            .iter()
+            .zip(&intervals)
+            .map(|(record, interval_in_h)| record.pv_power_output * interval_in_h)
+            .sum();
+        capacities_kwh
+            .iter()
+            .map(|&capacity_kwh| {
+                let mut battery = Battery::new(capacity_kwh * 1000.0)?;
+                battery.max_power_w = self.battery.and_then(|battery| battery.max_power_w);
+                // Energy is either exported or retained in the battery if not consumed.
+                let unconsumed_wh: f64 = self
+                    .records
+                    .iter()
+                    .zip(&intervals)
+                    .zip(dispatch_battery(&self.records, &battery))
+                    .map(|((record, interval_in_h), state)| {
+                        (-record.net_load() * interval_in_h - state.charged_wh).max(0.0)
+                            + state.charged_wh
+                    })
+                    .sum();
+                Ok((
+                    capacity_kwh,
+                    self_consumption_ratio(produced_wh, unconsumed_wh),
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns how long after the first sunset, which is the first `Record` without power
+    /// output following one with power output, the modelled `Battery` covers the power
+    /// consumption before power must be imported from the grid. If the `Battery` lasts
+    /// the entire night, the duration of the night is returned.
+    /// Returns `None` if no `Battery` is modelled or there is no sunset.
+    pub fn evening_autonomy(&self) -> Option<Duration> {
+        let battery = self.battery.as_ref()?;
+        let sunset_index = self
+            .records
+            .windows(2)
+            .position(|pair| pair[0].pv_power_output > 0.0 && pair[1].pv_power_output <= 0.0)?
+            + 1;
+        let sunset = self.records[sunset_index].time_stamp;
+        let states = dispatch_battery(&self.records, battery);
+        let intervals = record_intervals_in_h(&self.records);
+        let mut night_end = sunset;
+        for index in sunset_index..self.records.len() {
+            let record = &self.records[index];
+            if record.pv_power_output > 0.0 {
+                break;
+            }
+            let deficit_wh = record.net_load() * intervals[index];
+            let discharged_wh = -states[index].charged_wh;
+            if deficit_wh > 0.0 && discharged_wh < deficit_wh {
+                // The battery is depleted partway through the interval.
+                let covered_in_h = intervals[index] * discharged_wh / deficit_wh;
+                return Some(
+                    record.time_stamp - sunset
+                        + Duration::milliseconds((covered_in_h * 3_600_000.0) as i64),
+                );
+            }
+            night_end =
+                record.time_stamp + Duration::milliseconds((intervals[index] * 3_600_000.0) as i64);
+        }
+        Some(night_end - sunset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::simulator_with_records;
+    use super::*;
+
+    #[test]
+    /// Tests if the `Battery` struct only accepts valid parameters.
+    fn test_battery_new() {
+        assert!(Battery::new(-1.0).is_err());
+        assert!(Battery::new(f64::NAN).is_err());
+        let mut battery = Battery::new(1000.0).unwrap();
+        assert!(battery.set_max_power(Some(-1.0)).is_err());
+        assert!(battery.set_max_power(Some(500.0)).is_ok());
+        assert!(battery.set_initial_charge(1000.1).is_err());
+        assert!(battery.set_initial_charge(-0.1).is_err());
+        assert!(battery.set_initial_charge(1000.0).is_ok());
+    }
+
+    #[test]
+    /// Tests if the function `dispatch_battery` respects the capacity and power limit.
+    fn test_dispatch_battery() {
+        let simulator = simulator_with_records(&[
+            (0.0, 3000.0),
+            (0.0, 3000.0),
+            (1000.0, 0.0),
+            (3000.0, 0.0),
+            (1000.0, 0.0),
+        ]);
+        let mut battery = Battery::new(4000.0).unwrap();
+        battery.set_max_power(Some(2000.0)).unwrap();
+        let soc: Vec<f64> = dispatch_battery(&simulator.records, &battery)
+            .iter()
+            .map(|state| state.soc_wh)
+            .collect();
+        assert_eq!(soc, vec![0.0, 2000.0, 4000.0, 3000.0, 1000.0]);
+    }
+
+    #[test]
+    /// Tests if the function `evening_autonomy` returns how long a full battery covers
+    /// the evening load.
+    fn test_evening_autonomy() {
+        let mut powers = vec![(0.0, 2000.0), (0.0, 2000.0)];
+        powers.extend(vec![(1000.0, 0.0); 6]);
+        let mut simulator = simulator_with_records(&powers);
+        assert_eq!(simulator.evening_autonomy(), None);
+        simulator.set_battery(Some(Battery::new(4000.0).unwrap()));
+        let soc = simulator.battery_soc_wh().unwrap();
+        assert_eq!(soc[2].1, 4000.0);
+        assert_eq!(simulator.evening_autonomy(), Some(Duration::hours(4)));
+        // Depletion partway through an interval.
+        simulator.set_battery(Some(Battery::new(3500.0).unwrap()));
+        assert_eq!(simulator.evening_autonomy(), Some(Duration::minutes(210)));
+        // The battery lasts the entire night.
+        let mut battery = Battery::new(10_000.0).unwrap();
+        battery.set_initial_charge(10_000.0).unwrap();
+        simulator.set_battery(Some(battery));
+        assert_eq!(simulator.evening_autonomy(), Some(Duration::hours(6)));
+        // No sunset.
+        let mut simulator = simulator_with_records(&[(1000.0, 0.0); 5]);
+        simulator.set_battery(Some(Battery::new(4000.0).unwrap()));
+        assert_eq!(simulator.evening_autonomy(), None);
+    }
+
+    #[test]
+    /// Tests if the function `battery_cycle_count` counts a full charge and discharge as
+    /// one cycle.
+    fn test_battery_cycle_count() {
+        let mut powers = vec![(0.0, 2000.0); 2];
+        powers.extend(vec![(1000.0, 0.0); 4]);
+        let mut simulator = simulator_with_records(&powers);
+        assert_eq!(simulator.battery_cycle_count(), 0.0);
+        simulator.set_battery(Some(Battery::new(4000.0).unwrap()));
+        assert!((simulator.battery_cycle_count() - 1.0).abs() < 1e-9);
+        // Half of a larger battery is cycled.
+        simulator.set_battery(Some(Battery::new(8000.0).unwrap()));
+        assert!((simulator.battery_cycle_count() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    /// Tests if the function `battery_sizing_sweep` yields a non-decreasing self-consumption
+    /// ratio for increasing capacities.
+    fn test_battery_sizing_sweep() {
+        let mut powers = vec![(500.0, 0.0); 6];
+        powers.extend(vec![(500.0, 2500.0); 10]);
+        powers.extend(vec![(1000.0, 0.0); 8]);
+        let mut simulator = simulator_with_records(&powers);
+        let capacities = [0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0];
+        let sweep = simulator.battery_sizing_sweep(&capacities).unwrap();
+        assert_eq!(sweep.len(), capacities.len());
+        for ((capacity, _), expected) in sweep.iter().zip(&capacities) {
+            assert_eq!(capacity, expected);
+        }
+        for pair in sweep.windows(2) {
+            assert!(pair[1].1 >= pair[0].1);
+        }
+        // Without battery 5 of the 25 kWh produced are consumed directly.
+        assert!((sweep[0].1 - 0.2).abs() < 1e-9);
+        // The surplus exceeds the entire evening consumption of 8 kWh.
+        assert!((sweep[4].1 - 13.0 / 25.0).abs() < 1e-9);
+        assert_eq!(sweep[4].1, sweep[6].1);
+        // The power limit of the modelled battery applies to all candidates.
+        let mut battery = Battery::new(1000.0).unwrap();
+        battery.set_max_power(Some(500.0)).unwrap();
+        simulator.set_battery(Some(battery));
+        let limited = simulator.battery_sizing_sweep(&[10.0]).unwrap();
+        assert!(limited[0].1 < sweep[4].1);
+    }
+
+    #[test]
+    /// Tests if the function `battery_sizing_sweep` will correctly fail on invalid capacities.
+    fn test_battery_sizing_sweep_invalid() {
+        let simulator = simulator_with_records(&[(500.0, 1000.0)]);
+        assert!(simulator.battery_sizing_sweep(&[-1.0]).is_err());
+        assert!(simulator.battery_sizing_sweep(&[1.0, f64::NAN]).is_err());
+    }
+}
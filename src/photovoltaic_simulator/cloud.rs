@@ -0,0 +1,133 @@
+//! The `cloud` module allows simulation of passing clouds attenuating the power output of
+//! the photovoltaic component for stretches of time.
+
+use super::super::pv_error::PvError;
+use super::PvSimulator;
+use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The duration in seconds of the stretches of time a cloud cover is constant for.
+const CLOUD_DURATION_IN_S: i64 = 15 * 60;
+/// The range of the fraction of the power output remaining below a cloud.
+const CLOUDY_FRACTION: (f64, f64) = (0.3, 0.7);
+
+/// The `CloudCover` attenuates the power output during stretches of time, which are cloudy
+/// with a probability equal to the cloudiness.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) struct CloudCover {
+    cloudiness: f64,
+    seed: u64,
+}
+
+impl CloudCover {
+    /// Returns the fraction of the power output remaining below the clouds at the specified
+    /// time point. All time points within the same stretch of time share the same fraction,
+    /// which only depends on the seed, so the cloud cover is reproducible.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the simulated time point
+    pub fn unclouded_fraction(&self, time: DateTime<Utc>) -> f64 {
+        let stretch = time.timestamp().div_euclid(CLOUD_DURATION_IN_S);
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(stretch as u64));
+        if rng.gen_bool(self.cloudiness) {
+            rng.gen_range(CLOUDY_FRACTION.0, CLOUDY_FRACTION.1)
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns a `CloudCover` of the same cloudiness, but with an independent cloud cover
+    /// drawn from the specified random number generator.
+    ///
+    /// # Parameters
+    ///
+    /// * `rng` - the random number generator
+    pub fn redrawn<R: Rng>(&self, rng: &mut R) -> CloudCover {
+        CloudCover {
+            cloudiness: self.cloudiness,
+            seed: rng.gen(),
+        }
+    }
+}
+
+impl PvSimulator {
+    /// Sets the cloudiness, which is the probability of each stretch of 15 minutes being
+    /// cloudy. Clouds reduce the power output by 30-70% for the whole stretch of time.
+    /// The cloud cover is drawn from the random number generator of the `PvSimulator`,
+    /// so `PvSimulator`s with the same seed simulate the same cloud cover.
+    /// Per default the sky is clear.
+    /// Fails if the cloudiness is not within [0, 1].
+    ///
+    /// # Parameters
+    ///
+    /// * `cloudiness` - the probability of a stretch of time being cloudy, if any
+    pub fn set_cloudiness(&mut self, cloudiness: Option<f64>) -> Result<(), PvError> {
+        self.cloud_cover = match cloudiness {
+            Some(cloudiness) if (0.0..=1.0).contains(&cloudiness) => Some(CloudCover {
+                cloudiness,
+                seed: self.rng.borrow_mut().gen(),
+            }),
+            Some(cloudiness) => {
+                return Err(PvError::InternalError(format!(
+                    "{} is not a valid cloudiness.",
+                    cloudiness
+                )))
+            },
+            None => None,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::BrokerMessage;
+    use super::*;
+    use chrono::{Duration, TimeZone, Timelike};
+
+    #[test]
+    /// Tests if the cloud cover reduces the mean power output around midday without shifting
+    /// the time of the peak output.
+    fn test_set_cloudiness() {
+        let mut simulator = PvSimulator::with_seed("", 42);
+        assert!(simulator.set_cloudiness(Some(-0.1)).is_err());
+        assert!(simulator.set_cloudiness(Some(1.1)).is_err());
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        // Mean output for each hour of the day over a year.
+        let hourly_means = |simulator: &PvSimulator| {
+            let start = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+            let mut sums = [0.0; 24];
+            for minutes in (0..365 * 24 * 60).step_by(10) {
+                let time = start + Duration::minutes(minutes);
+                let record = simulator
+                    .message_to_record(BrokerMessage::new(0.0, time).unwrap())
+                    .unwrap();
+                assert!(record.pv_power_output >= 0.0);
+                if time.hour() < 5 || time.hour() >= 21 {
+                    assert_eq!(record.pv_power_output, 0.0);
+                }
+                sums[time.hour() as usize] += record.pv_power_output;
+            }
+            sums
+        };
+        let peak_hour = |means: &[f64; 24]| {
+            (0..24)
+                .max_by(|a, b| means[*a].partial_cmp(&means[*b]).unwrap())
+                .unwrap()
+        };
+        let clear = hourly_means(&simulator);
+        simulator.set_cloudiness(Some(0.8)).unwrap();
+        let cloudy = hourly_means(&simulator);
+        assert!(cloudy[12] < 0.75 * clear[12]);
+        assert_eq!(peak_hour(&cloudy), peak_hour(&clear));
+        // Subsequent time points are attenuated equally.
+        let cloud_cover = simulator.cloud_cover.unwrap();
+        let time = Utc.with_ymd_and_hms(2021, 6, 21, 12, 0, 0).unwrap();
+        assert_eq!(
+            cloud_cover.unclouded_fraction(time),
+            cloud_cover.unclouded_fraction(time + Duration::minutes(5))
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! The `derived` module allows recomputation of the quantities derived from the simulated
+//! production and consumption of the `Record`s observed by a `PvSimulator`.
+
+use super::super::pv_error::PvError;
+use super::{Battery, PvSimulator, Record};
+
+/// The `DerivedConfig` specifies the parameters of all quantities derived from the simulated
+/// production and consumption.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct DerivedConfig {
+    /// The maximum power in watt that may be exported to the grid, if any.
+    pub export_limit_w: Option<f64>,
+    /// The `Battery` storing surplus power output for later consumption, if any.
+    pub battery: Option<Battery>,
+}
+
+impl PvSimulator {
+    /// Recomputes the AC power output and the total power output of all observed `Record`s
+    /// from their DC power output and power consumption using the specified configuration,
+    /// which also applies to subsequently observed `Record`s. Grid import and export, costs
+    /// and the state of charge of the `Battery` follow from the recomputed `Record`s.
+    /// The quality of a `Record` is only updated if it is curtailed by the new export limit.
+    /// Fails if the export limit is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `config` - the parameters of the derived quantities
+    pub fn recompute_derived(&mut self, config: DerivedConfig) -> Result<(), PvError> {
+        self.set_export_limit(config.export_limit_w)?;
+        self.set_battery(config.battery);
+        for index in 0..self.records.len() {
+            let record = self.records[index];
+            let ac_output = self.inverter_output(record.dc_output);
+            self.records[index] = Record {
+                pv_power_output: ac_output,
+                total_power_output: ac_output - record.meter_power_consumption,
+                ..record
+            }
+            .with_export_limit(self.export_limit_w);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::float_compare_non_exact;
+    use super::super::RecordQuality;
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    /// Tests if the function `recompute_derived` applies a new export limit without altering
+    /// the simulated production and consumption.
+    fn test_recompute_derived() {
+        let mut simulator = PvSimulator::new("");
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 10, 0, 0).unwrap();
+        simulator.records = [(500.0, 3000.0), (1000.0, 1200.0), (2000.0, 500.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, (consumption, output))| {
+                Record::new(start + Duration::hours(i as i64), *consumption, *output)
+            })
+            .collect();
+        let original = simulator.records.clone();
+        let original_export = simulator.energy_summary().grid_export_kwh;
+        assert!(simulator
+            .recompute_derived(DerivedConfig {
+                export_limit_w: Some(-1.0),
+                battery: None,
+            })
+            .is_err());
+        simulator
+            .recompute_derived(DerivedConfig {
+                export_limit_w: Some(1000.0),
+                battery: Some(Battery::new(1000.0).unwrap()),
+            })
+            .unwrap();
+        // The surplus of 2500 W is curtailed to 1000 W, while 200 W remain unaffected.
+        let totals: Vec<f64> = simulator
+            .records
+            .iter()
+            .map(|record| record.total_power_output)
+            .collect();
+        assert_eq!(totals, vec![1000.0, 200.0, -1500.0]);
+        assert_eq!(simulator.records[0].pv_power_output, 1500.0);
+        assert_eq!(simulator.records[0].quality(), RecordQuality::Clipped);
+        assert_eq!(simulator.records[1].quality(), RecordQuality::Good);
+        assert!(float_compare_non_exact(
+            original_export - simulator.energy_summary().grid_export_kwh,
+            1.5
+        ));
+        assert!(simulator.battery_soc_wh().is_some());
+        for (recomputed, original) in simulator.records.iter().zip(&original) {
+            assert_eq!(recomputed.time_stamp, original.time_stamp);
+            assert_eq!(
+                recomputed.meter_power_consumption,
+                original.meter_power_consumption
+            );
+            assert_eq!(recomputed.dc_output, original.dc_output);
+        }
+        // Lifting the limit restores the original power output.
+        simulator
+            .recompute_derived(DerivedConfig::default())
+            .unwrap();
+        for (recomputed, original) in simulator.records.iter().zip(&original) {
+            assert_eq!(recomputed.pv_power_output, original.pv_power_output);
+            assert_eq!(recomputed.total_power_output, original.total_power_output);
+        }
+        assert!(simulator.battery_soc_wh().is_none());
+    }
+}
@@ -0,0 +1,375 @@
+//! The `model` module allows replacing the curve of the simulated power output, e.g. by
+//! measured irradiance curves or simpler models.
+
+use super::super::pv_error::PvError;
+use super::seasonal::Seasonality;
+use super::solar::solar_dawn_dusk;
+use super::table::OutputTable;
+use super::{
+    clear_sky_output, normalised_time_of_day, PvSimulator, DEFAULT_DAYLIGHT_IN_H,
+    DEFAULT_PEAK_SCALING,
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use std::any::Any;
+use std::fmt;
+
+/// A `PvModel` simulates the power output of a photovoltaic component under a clear sky.
+pub trait PvModel: fmt::Debug + Send + Sync + PvModelObject {
+    /// Returns the power output in watt at the specified time point, which must be a
+    /// positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the simulated time point
+    fn output_watts(&self, time: DateTime<Utc>) -> f64;
+}
+
+/// The `PvModelObject` allows cloning, comparing and downcasting of boxed `PvModel`s.
+/// It is implemented for every `PvModel` that implements `Clone` and `PartialEq`.
+pub trait PvModelObject {
+    /// Returns a boxed copy of the model.
+    fn clone_box(&self) -> Box<dyn PvModel>;
+
+    /// Returns the model as `Any`, so it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the model as mutable `Any`, so it can be downcast to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Checks if the model equals the other model, which requires both to be of the
+    /// same type.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - the model to compare to
+    fn dyn_eq(&self, other: &dyn PvModel) -> bool;
+}
+
+impl<M: PvModel + Clone + PartialEq + 'static> PvModelObject for M {
+    fn clone_box(&self) -> Box<dyn PvModel> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &dyn PvModel) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<M>()
+            .is_some_and(|other| self == other)
+    }
+}
+
+impl Clone for Box<dyn PvModel> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// The `KumaraswamyModel` approximates the power output between dawn and dusk by the
+/// probability density function of a Kumaraswamy distribution. This is the default model
+/// of a `PvSimulator`, which configures its location, seasonality and output table.
+#[derive(Debug, PartialEq, Clone)]
+pub struct KumaraswamyModel {
+    pub(super) dawn_in_h: f64,
+    pub(super) dusk_in_h: f64,
+    pub(super) peak_scaling: f64,
+    pub(super) location: Option<(f64, f64)>,
+    pub(super) seasonality: Option<Seasonality>,
+    pub(super) output_table: Option<OutputTable>,
+}
+
+impl KumaraswamyModel {
+    /// Creates a new `KumaraswamyModel`.
+    /// Fails if dawn does not precede dusk, if either is not within [0, 24) or if the scaling
+    /// is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `dawn_in_h` - the start of the power output in hours from midnight
+    /// * `dusk_in_h` - the end of the power output in hours from midnight
+    /// * `peak_scaling` - the scaling of the power output
+    pub fn new(dawn_in_h: f64, dusk_in_h: f64, peak_scaling: f64) -> Result<Self, PvError> {
+        if !((0.0..24.0).contains(&dawn_in_h)
+            && (0.0..24.0).contains(&dusk_in_h)
+            && dawn_in_h < dusk_in_h)
+        {
+            return Err(PvError::InternalError(format!(
+                "{}-{} is not a valid range of daylight hours.",
+                dawn_in_h, dusk_in_h
+            )));
+        }
+        let mut model = KumaraswamyModel {
+            dawn_in_h,
+            dusk_in_h,
+            ..KumaraswamyModel::default()
+        };
+        model.set_peak_scaling(peak_scaling)?;
+        Ok(model)
+    }
+
+    /// Sets the scaling of the power output.
+    /// Fails if the scaling is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `peak_scaling` - the scaling of the power output
+    pub fn set_peak_scaling(&mut self, peak_scaling: f64) -> Result<(), PvError> {
+        if peak_scaling.is_finite() && peak_scaling.is_sign_positive() {
+            self.peak_scaling = peak_scaling;
+            Ok(())
+        } else {
+            Err(PvError::InternalError(format!(
+                "{} is not a positive finite number.",
+                peak_scaling
+            )))
+        }
+    }
+
+    /// Returns the scaling of the power output.
+    pub fn peak_scaling(&self) -> f64 {
+        self.peak_scaling
+    }
+
+    /// Returns the local mean solar time of the site at the specified time point, which dawn
+    /// and dusk refer to.
+    /// The solar time advances by 4 minutes per degree of longitude east of the prime meridian,
+    /// which is equivalent to shifting dawn and dusk by `-longitude * 4` minutes in UTC.
+    /// As the whole time point is shifted, daylight that wraps past midnight UTC is attributed
+    /// to the correct solar day. Without a location UTC is used.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the simulated time point
+    fn solar_time(&self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self.location {
+            Some((_, longitude)) => {
+                time.naive_utc() + Duration::nanoseconds((longitude * 240.0e9).round() as i64)
+            },
+            None => time.naive_utc(),
+        }
+    }
+
+    /// Returns dawn and dusk in hours from midnight at the specified date.
+    /// If the location of the site is known, dawn and dusk are computed from its latitude
+    /// in local solar time (see `solar_time`).
+    /// Otherwise the fixed dawn and dusk of the model apply, which are shortened
+    /// in winter according to the `Seasonality`, if any.
+    ///
+    /// # Parameters
+    ///
+    /// * `date` - the simulated date
+    pub(super) fn daylight_in_h(&self, date: NaiveDate) -> (f64, f64) {
+        match self.location {
+            Some((latitude, _)) => {
+                let (dawn, dusk) = solar_dawn_dusk(latitude, date);
+                (normalised_time_of_day(dawn), normalised_time_of_day(dusk))
+            },
+            None => match self.seasonality {
+                Some(seasonality) => {
+                    seasonality.daylight_in_h((self.dawn_in_h, self.dusk_in_h), date)
+                },
+                None => (self.dawn_in_h, self.dusk_in_h),
+            },
+        }
+    }
+
+    /// Returns `true` if the power output is looked up in a precomputed table.
+    pub(super) fn is_interpolated(&self) -> bool {
+        self.output_table.is_some()
+    }
+}
+
+impl Default for KumaraswamyModel {
+    fn default() -> Self {
+        KumaraswamyModel {
+            dawn_in_h: DEFAULT_DAYLIGHT_IN_H.0,
+            dusk_in_h: DEFAULT_DAYLIGHT_IN_H.1,
+            peak_scaling: DEFAULT_PEAK_SCALING,
+            location: None,
+            seasonality: None,
+            output_table: None,
+        }
+    }
+}
+
+impl PvModel for KumaraswamyModel {
+    fn output_watts(&self, time: DateTime<Utc>) -> f64 {
+        let solar_time = self.solar_time(time);
+        let time_of_day_in_h = normalised_time_of_day(solar_time.time());
+        let daylight_in_h = self.daylight_in_h(solar_time.date());
+        let peak_scaling = self.seasonal_peak_scaling(time);
+        match &self.output_table {
+            Some(table) => table.lookup(time_of_day_in_h, daylight_in_h) * peak_scaling,
+            None => clear_sky_output(time_of_day_in_h, daylight_in_h, peak_scaling),
+        }
+    }
+}
+
+impl PvSimulator {
+    /// Creates a new `PvSimulator` like `new`, but simulating the power output with the
+    /// specified model. See `set_model` for details.
+    ///
+    /// # Parameters
+    ///
+    /// * `broker_url` - the url of the broker
+    /// * `model` - the model of the power output
+    pub fn with_model<U: Into<String>, M: PvModel + 'static>(broker_url: U, model: M) -> Self {
+        let mut simulator = PvSimulator::new(broker_url);
+        simulator.set_model(model);
+        simulator
+    }
+
+    /// Sets the model of the power output, which replaces the current model
+    /// including its daylight hours, seasonality and output table.
+    /// A `KumaraswamyModel` adopts the location of the site, while other models cannot be
+    /// configured by the `PvSimulator`.
+    /// Jitter, shading, clouds and the inverter still apply to the output of the model.
+    /// Per default a `KumaraswamyModel` is used.
+    ///
+    /// # Parameters
+    ///
+    /// * `model` - the model of the power output
+    pub fn set_model<M: PvModel + 'static>(&mut self, model: M) {
+        self.model = Box::new(model);
+        let location = self.location;
+        if let Ok(model) = self.kumaraswamy_mut() {
+            model.location = location;
+        }
+    }
+
+    /// Returns the `KumaraswamyModel` of the power output, unless another model is set.
+    pub(super) fn kumaraswamy(&self) -> Option<&KumaraswamyModel> {
+        self.model.as_any().downcast_ref()
+    }
+
+    /// Returns the mutable `KumaraswamyModel` of the power output.
+    /// Fails if another model is set.
+    pub(super) fn kumaraswamy_mut(&mut self) -> Result<&mut KumaraswamyModel, PvError> {
+        self.model.as_any_mut().downcast_mut().ok_or_else(|| {
+            PvError::InternalError(
+                "The power output is not simulated by a KumaraswamyModel.".to_string(),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::{BrokerMessage, Meter};
+    use super::super::super::simulate_pv_in_memory;
+    use super::super::super::simulated_time::SimulatedDateTime;
+    use super::*;
+    use chrono::{Duration, TimeZone, Timelike};
+
+    /// A model with constant power output during the day.
+    #[derive(Debug, PartialEq, Clone)]
+    struct ConstantModel(f64);
+
+    impl PvModel for ConstantModel {
+        fn output_watts(&self, time: DateTime<Utc>) -> f64 {
+            if (6..18).contains(&time.hour()) {
+                self.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[test]
+    /// Tests if the function `with_model` simulates the power output with the specified model.
+    fn test_with_model() {
+        let meter = Meter::with_seed(9000.0, "", 42).unwrap();
+        let mut simulator = PvSimulator::with_model("", ConstantModel(1234.0));
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let simulation_time =
+            SimulatedDateTime::starting_at(start, Duration::minutes(10), Duration::days(1));
+        simulate_pv_in_memory(&meter, &mut simulator, simulation_time).unwrap();
+        assert_eq!(simulator.records().len(), 144);
+        for record in simulator.records() {
+            if (6..18).contains(&record.time_stamp.hour()) {
+                assert_eq!(record.pv_power_output, 1234.0);
+            } else {
+                assert_eq!(record.pv_power_output, 0.0);
+            }
+        }
+    }
+
+    #[test]
+    /// Tests if the `KumaraswamyModel` validates its parameters and matches the default
+    /// power output.
+    fn test_kumaraswamy_model() {
+        assert!(KumaraswamyModel::new(21.0, 5.0, 1650.0).is_err());
+        assert!(KumaraswamyModel::new(5.0, 24.0, 1650.0).is_err());
+        assert!(KumaraswamyModel::new(5.0, 21.0, -1.0).is_err());
+        let model = KumaraswamyModel::new(5.0, 21.0, 1650.0).unwrap();
+        let mut default = PvSimulator::new("");
+        default.set_jitter_amplitude(0.0).unwrap();
+        let mut modelled = PvSimulator::with_model("", model);
+        modelled.set_jitter_amplitude(0.0).unwrap();
+        let output_at = |simulator: &PvSimulator, time| {
+            simulator
+                .message_to_record(BrokerMessage::new(0.0, time).unwrap())
+                .unwrap()
+                .pv_power_output
+        };
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        for time in SimulatedDateTime::starting_at(start, Duration::hours(1), Duration::days(1)) {
+            assert_eq!(output_at(&default, time), output_at(&modelled, time));
+        }
+        // Invalid power output is reported.
+        let invalid = PvSimulator::with_model("", ConstantModel(f64::NAN));
+        assert!(invalid
+            .message_to_record(BrokerMessage::new(0.0, start + Duration::hours(12)).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    /// Tests if the built-in configuration is rejected for other models and the yield
+    /// sanity check follows the model of the power output.
+    fn test_set_model() {
+        let meter = Meter::with_seed(9000.0, "", 42).unwrap();
+        let mut simulator = PvSimulator::with_model("", ConstantModel(1234.0));
+        simulator.set_location(Some((52.5, 13.4))).unwrap();
+        assert!(simulator.set_peak_scaling(2000.0).is_err());
+        assert!(simulator.precompute_table(Duration::minutes(1)).is_err());
+        assert!(simulator.set_seasonality(None).is_err());
+        assert_eq!(simulator.peak_scaling(), None);
+        assert_eq!(simulator.output_table(), None);
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let simulation_time =
+            SimulatedDateTime::starting_at(start, Duration::minutes(10), Duration::days(1));
+        simulate_pv_in_memory(&meter, &mut simulator, simulation_time).unwrap();
+        assert!((simulator.yield_sanity_check().ratio - 1.0).abs() < 1e-9);
+        // The default model adopts the location of the site.
+        simulator.set_model(KumaraswamyModel::default());
+        assert_eq!(
+            simulator.kumaraswamy().unwrap().location,
+            Some((52.5, 13.4))
+        );
+        simulator.set_peak_scaling(2000.0).unwrap();
+        assert_eq!(simulator.peak_scaling(), Some(2000.0));
+    }
+
+    #[test]
+    /// Tests if the function `dyn_eq` only considers models of the same type and value equal.
+    fn test_dyn_eq() {
+        let model = ConstantModel(1234.0);
+        assert!(model.dyn_eq(&ConstantModel(1234.0)));
+        assert!(!model.dyn_eq(&ConstantModel(-1234.0)));
+        assert!(!model.dyn_eq(&KumaraswamyModel::default()));
+        assert!(PvSimulator::with_model("", model.clone()) == PvSimulator::with_model("", model));
+        assert!(
+            PvSimulator::with_model("", ConstantModel(0.0))
+                != PvSimulator::with_model("", ConstantModel(1.0))
+        );
+    }
+}
@@ -0,0 +1,509 @@
+//! The `output` module allows serialisation of `Record`s in different file formats.
+
+use super::super::pv_error::PvError;
+use super::{EnergySummary, PvSimulator, Record};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{read, read_to_string, write, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The header row of `Record`s serialised as comma separated values.
+const CSV_HEADER: &str =
+    "time_stamp,meter_power_consumption,pv_power_output,total_power_output,dc_output";
+
+/// The header row of `DailySummary`s serialised as comma separated values.
+const DAILY_SUMMARY_CSV_HEADER: &str =
+    "date,consumed_kwh,produced_kwh,peak_output_w,peak_output_time,self_consumption_ratio";
+
+/// The file extension appended to the path of a records file to create its checksum sidecar file.
+const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// A `Manifest` describes a simulation run for archival alongside its `Record`s.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The version of the simulation software.
+    pub software_version: String,
+    /// The configuration of the photovoltaic component.
+    pub config: SimulatorConfig,
+    /// The time stamp of the first `Record`, if any.
+    pub start_time: Option<DateTime<Utc>>,
+    /// The time stamp of the last `Record`, if any.
+    pub end_time: Option<DateTime<Utc>>,
+    /// The number of `Record`s.
+    pub record_count: usize,
+    /// The energy summary of all `Record`s.
+    pub energy_summary: EnergySummary,
+}
+
+/// The `SimulatorConfig` contains all parameters of a `PvSimulator` affecting the
+/// simulated values.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct SimulatorConfig {
+    /// The fraction of the DC output converted to AC output.
+    pub inverter_efficiency: f64,
+    /// The maximum AC output in watt, if any.
+    pub inverter_limit_w: Option<f64>,
+    /// The relative amplitude of the random noise.
+    pub jitter_amplitude: f64,
+    /// The deadband in watt, if any.
+    pub deadband_w: Option<f64>,
+    /// The maximum power exported to the grid in watt, if any.
+    pub export_limit_w: Option<f64>,
+    /// The scaling of the simulated power output, unless it is simulated by another model
+    /// than the `KumaraswamyModel`.
+    pub peak_scaling: Option<f64>,
+    /// The capacity of the modelled battery in Wh, if any.
+    pub battery_capacity_wh: Option<f64>,
+    /// The albedo of the ground.
+    #[serde(default)]
+    pub albedo: f64,
+}
+
+impl PvSimulator {
+    /// Returns the `Manifest` describing the current simulation run.
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            software_version: env!("CARGO_PKG_VERSION").to_string(),
+            config: SimulatorConfig {
+                inverter_efficiency: self.inverter_efficiency,
+                inverter_limit_w: self.inverter_limit,
+                jitter_amplitude: self.jitter_amplitude,
+                deadband_w: self.deadband,
+                export_limit_w: self.export_limit_w,
+                peak_scaling: self.peak_scaling(),
+                battery_capacity_wh: self.battery.map(|battery| battery.capacity_wh()),
+                albedo: self.albedo,
+            },
+            start_time: self.records.first().map(|record| record.time_stamp),
+            end_time: self.records.last().map(|record| record.time_stamp),
+            record_count: self.records.len(),
+            energy_summary: self.energy_summary(),
+        }
+    }
+
+    /// Writes the `Manifest` describing the current simulation run as JSON to the
+    /// specified file.
+    /// Fails if the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the manifest file
+    pub fn write_manifest<P: AsRef<Path>>(&self, path: P) -> Result<(), PvError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, &self.manifest()).map_err(serialisation_error)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the `DailySummary` of each simulated day as comma separated values with a header
+    /// row to the specified file. Dates are formatted according to ISO 8601 and time stamps
+    /// according to RFC 3339.
+    /// Fails if the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the CSV file
+    pub fn write_daily_summary_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), PvError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", DAILY_SUMMARY_CSV_HEADER)?;
+        for summary in self.daily_summaries() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                summary.date,
+                summary.consumed_kwh,
+                summary.produced_kwh,
+                summary.peak_output_w,
+                summary.peak_output_time.to_rfc3339(),
+                summary.self_consumption_ratio
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the `Record`s as GeoJSON `FeatureCollection` to the specified file.
+    /// Each `Record` is a point feature at the location of the site with the values
+    /// of the `Record` as properties.
+    /// Fails if no location is set or the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the GeoJSON file
+    pub fn write_records_geojson<P: AsRef<Path>>(&self, path: P) -> Result<(), PvError> {
+        let (latitude, longitude) = self.location.ok_or_else(|| {
+            PvError::InternalError("No location was specified for the site.".to_string())
+        })?;
+        let features = self
+            .records
+            .iter()
+            .map(|record| {
+                Ok(json!({
+                    "type": "Feature",
+                    // GeoJSON positions are specified as longitude followed by latitude.
+                    "geometry": {"type": "Point", "coordinates": [longitude, latitude]},
+                    "properties": serde_json::to_value(record).map_err(serialisation_error)?,
+                }))
+            })
+            .collect::<Result<Vec<Value>, PvError>>()?;
+        let feature_collection = json!({"type": "FeatureCollection", "features": features});
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, &feature_collection).map_err(serialisation_error)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The `OutputFormat`s `Record`s can be written in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    /// A single compact JSON list containing all `Record`s.
+    Json,
+    /// A single human readable JSON list containing all `Record`s with indentation
+    /// and line breaks.
+    PrettyJson,
+    /// Newline delimited JSON with one `Record` per line.
+    JsonLines,
+    /// Comma separated values with a header row and one `Record` per line.
+    /// Time stamps are formatted according to RFC 3339.
+    Csv,
+}
+
+impl PvSimulator {
+    /// Writes all observed `Record`s to the specified file in the specified format
+    /// and the SHA-256 checksum of the written file to a sidecar file with an additional
+    /// `.sha256` extension.
+    /// Fails if the records or the checksum cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the output file
+    /// * `format` - the format of the output file
+    pub fn write_records_with_checksum<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: OutputFormat,
+    ) -> Result<(), PvError> {
+        self.write_records(path.as_ref(), format)?;
+        let checksum = sha256_hex(&read(path.as_ref())?);
+        write(checksum_path(path), checksum)?;
+        Ok(())
+    }
+
+    /// Returns `true` if the SHA-256 checksum of the specified records file matches
+    /// the checksum stored in its sidecar file as written by `write_records_with_checksum`.
+    /// Fails if either file cannot be read.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the records file
+    pub fn verify_records_file<P: AsRef<Path>>(path: P) -> Result<bool, PvError> {
+        let expected_checksum = read_to_string(checksum_path(path.as_ref()))?;
+        let actual_checksum = sha256_hex(&read(path)?);
+        Ok(expected_checksum.trim() == actual_checksum)
+    }
+}
+
+/// Returns the path of the checksum sidecar file belonging to the specified records file.
+///
+/// # Parameters
+///
+/// * `path` - the path to the records file
+fn checksum_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut checksum_path: OsString = path.as_ref().as_os_str().to_owned();
+    checksum_path.push(".");
+    checksum_path.push(CHECKSUM_EXTENSION);
+    PathBuf::from(checksum_path)
+}
+
+/// Returns the SHA-256 checksum of the specified bytes as lowercase hexadecimal string.
+///
+/// # Parameters
+///
+/// * `bytes` - the bytes to compute the checksum of
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Serialises the `Record`s in the specified format to the writer.
+/// Fails if serialisation or writing fails.
+///
+/// # Parameters
+///
+/// * `records` - the `Record`s to serialise
+/// * `writer` - the writer to serialise to
+/// * `format` - the output format
+pub(super) fn write_records_in_format<W: Write>(
+    records: &[Record],
+    writer: &mut W,
+    format: OutputFormat,
+) -> Result<(), PvError> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer(writer, records).map_err(serialisation_error)?;
+        },
+        OutputFormat::PrettyJson => {
+            serde_json::to_writer_pretty(writer, records).map_err(serialisation_error)?;
+        },
+        OutputFormat::JsonLines => {
+            for record in records {
+                serde_json::to_writer(&mut *writer, record).map_err(serialisation_error)?;
+                writer.write_all(b"\n")?;
+            }
+        },
+        OutputFormat::Csv => {
+            writeln!(writer, "{}", CSV_HEADER)?;
+            for record in records {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    record.time_stamp.to_rfc3339(),
+                    record.meter_power_consumption,
+                    record.pv_power_output,
+                    record.total_power_output,
+                    record.dc_output
+                )?;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Converts a serialisation error into a `PvError`, retaining the distinction between
+/// input/output errors and actual serialisation errors.
+///
+/// # Parameters
+///
+/// * `error` - the serialisation error
+fn serialisation_error(error: serde_json::Error) -> PvError {
+    if error.is_io() {
+        PvError::IoError(error.into())
+    } else {
+        PvError::SerilisationError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::float_compare_non_exact;
+    use super::super::tests::simulator_with_records;
+    use super::*;
+    use std::fs::remove_file;
+
+    /// The power consumption and output of the `Record`s written by the tests.
+    const POWERS: [(f64, f64); 5] = [
+        (0.0, 50.5),
+        (100.0, 50.5),
+        (200.0, 50.5),
+        (300.0, 50.5),
+        (400.0, 50.5),
+    ];
+
+    #[test]
+    /// Tests if the function `write_records` writes valid JSON.
+    fn test_write_records_json() {
+        let simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_write_records.json");
+        simulator.write_records(&path, OutputFormat::Json).unwrap();
+        let records: Vec<Record> = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(records, simulator.records);
+        remove_file(path).expect("The test output file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_records` writes indented JSON if pretty printing is
+    /// requested, while the default JSON stays compact.
+    fn test_write_records_pretty_json() {
+        let simulator = simulator_with_records(&POWERS);
+        let compact_path =
+            std::env::temp_dir().join("pv_simulator_test_write_records_compact.json");
+        let pretty_path = std::env::temp_dir().join("pv_simulator_test_write_records_pretty.json");
+        simulator
+            .write_records(&compact_path, OutputFormat::Json)
+            .unwrap();
+        simulator
+            .write_records(&pretty_path, OutputFormat::PrettyJson)
+            .unwrap();
+        let compact = read_to_string(&compact_path).unwrap();
+        let pretty = read_to_string(&pretty_path).unwrap();
+        assert!(!compact.contains('\n'));
+        assert!(!compact.contains("  "));
+        assert!(pretty.contains('\n'));
+        assert!(pretty.contains("\n  {"));
+        let compact_records: Vec<Record> = serde_json::from_str(&compact).unwrap();
+        let pretty_records: Vec<Record> = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(compact_records, simulator.records);
+        assert_eq!(pretty_records, simulator.records);
+        remove_file(compact_path).expect("The test output file could not be removed.");
+        remove_file(pretty_path).expect("The test output file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_records` writes one JSON record per line.
+    fn test_write_records_json_lines() {
+        let simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_write_records.jsonl");
+        simulator
+            .write_records(&path, OutputFormat::JsonLines)
+            .unwrap();
+        let records: Vec<Record> = read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records, simulator.records);
+        remove_file(path).expect("The test output file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_records` writes comma separated values with a header.
+    fn test_write_records_csv() {
+        let simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_write_records.csv");
+        simulator.write_records(&path, OutputFormat::Csv).unwrap();
+        let content = read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), simulator.records.len() + 1);
+        assert_eq!(lines[0], CSV_HEADER);
+        for (line, record) in lines[1..].iter().zip(&simulator.records) {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), 5);
+            let time_stamp = DateTime::parse_from_rfc3339(fields[0]).unwrap();
+            assert_eq!(time_stamp, record.time_stamp);
+            assert_eq!(
+                fields[1].parse::<f64>().unwrap(),
+                record.meter_power_consumption
+            );
+            assert_eq!(fields[2].parse::<f64>().unwrap(), record.pv_power_output);
+            assert_eq!(fields[3].parse::<f64>().unwrap(), record.total_power_output);
+            assert_eq!(fields[4].parse::<f64>().unwrap(), record.dc_output);
+        }
+        remove_file(path).expect("The test output file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_records_as_csv` writes one row per `Record` and creates
+    /// missing parent directories.
+    fn test_write_records_as_csv() {
+        let simulator = simulator_with_records(&POWERS);
+        let directory = std::env::temp_dir().join("pv_simulator_test_write_records_as_csv");
+        let path = directory.join("records.csv");
+        simulator.write_records_as_csv(&path).unwrap();
+        let content = read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(
+            lines[0],
+            "time_stamp,meter_power_consumption,pv_power_output,total_power_output,dc_output"
+        );
+        assert_eq!(lines.len() - 1, simulator.records.len());
+        std::fs::remove_dir_all(directory)
+            .expect("The test output directory could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `verify_records_file` detects corrupted records files.
+    fn test_verify_records_file() {
+        let simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_verify_records_file.json");
+        simulator
+            .write_records_with_checksum(&path, OutputFormat::Json)
+            .unwrap();
+        assert!(checksum_path(&path).exists());
+        assert!(PvSimulator::verify_records_file(&path).unwrap());
+        let mut content = read(&path).unwrap();
+        content[1] ^= 1;
+        write(&path, content).unwrap();
+        assert!(!PvSimulator::verify_records_file(&path).unwrap());
+        remove_file(checksum_path(&path)).expect("The test checksum file could not be removed.");
+        remove_file(path).expect("The test output file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_manifest` writes the record count and energy totals.
+    fn test_write_manifest() {
+        let simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_manifest.json");
+        simulator.write_manifest(&path).unwrap();
+        let manifest: Manifest = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(manifest, simulator.manifest());
+        assert_eq!(manifest.software_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.record_count, 5);
+        assert_eq!(manifest.start_time, Some(simulator.records[0].time_stamp));
+        assert_eq!(manifest.end_time, Some(simulator.records[4].time_stamp));
+        // Five records in hourly intervals with a total of 1000 W consumption
+        // and 252.5 W output.
+        assert!(float_compare_non_exact(
+            manifest.energy_summary.consumed_kwh,
+            1000.0 / 1000.0
+        ));
+        assert!(float_compare_non_exact(
+            manifest.energy_summary.produced_kwh,
+            252.5 / 1000.0
+        ));
+        assert_eq!(manifest.config.peak_scaling, simulator.peak_scaling());
+        remove_file(path).expect("The test manifest file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_records_geojson` writes every record as point feature
+    /// at the location of the site.
+    fn test_write_records_geojson() {
+        let mut simulator = simulator_with_records(&POWERS);
+        let path = std::env::temp_dir().join("pv_simulator_test_records.geojson");
+        assert!(simulator.write_records_geojson(&path).is_err());
+        simulator.set_location(Some((52.5, 13.4))).unwrap();
+        simulator.write_records_geojson(&path).unwrap();
+        let geojson: Value = serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), simulator.records.len());
+        for (feature, record) in features.iter().zip(simulator.records.iter()) {
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["geometry"]["type"], "Point");
+            assert_eq!(feature["geometry"]["coordinates"], json!([13.4, 52.5]));
+            let properties: Record = serde_json::from_value(feature["properties"].clone()).unwrap();
+            assert_eq!(&properties, record);
+        }
+        remove_file(path).expect("The test GeoJSON file could not be removed.");
+    }
+
+    #[test]
+    /// Tests if the function `write_daily_summary_csv` writes one row per simulated day.
+    fn test_write_daily_summary_csv() {
+        let mut simulator = simulator_with_records(&POWERS);
+        let first_day = simulator.records.clone();
+        simulator
+            .records
+            .extend(first_day.iter().map(|record| Record {
+                time_stamp: record.time_stamp + chrono::Duration::days(1),
+                ..*record
+            }));
+        let path = std::env::temp_dir().join("pv_simulator_test_daily_summary.csv");
+        simulator.write_daily_summary_csv(&path).unwrap();
+        let content = read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], DAILY_SUMMARY_CSV_HEADER);
+        for (line, summary) in lines[1..].iter().zip(simulator.daily_summaries()) {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), 6);
+            assert_eq!(fields[0], summary.date.to_string());
+            assert_eq!(fields[1].parse::<f64>().unwrap(), summary.consumed_kwh);
+            assert_eq!(fields[2].parse::<f64>().unwrap(), summary.produced_kwh);
+            assert_eq!(fields[3].parse::<f64>().unwrap(), summary.peak_output_w);
+            assert_eq!(
+                DateTime::parse_from_rfc3339(fields[4]).unwrap(),
+                summary.peak_output_time
+            );
+            assert_eq!(
+                fields[5].parse::<f64>().unwrap(),
+                summary.self_consumption_ratio
+            );
+        }
+        remove_file(path).expect("The test daily summary file could not be removed.");
+    }
+}
@@ -0,0 +1,106 @@
+//! The `parallel` module allows reductions over the `Record`s of a `PvSimulator` to be
+//! distributed over multiple worker threads if the `parallel` feature is enabled.
+//! Otherwise all reductions are performed serially.
+
+use super::analysis::record_intervals_in_h;
+#[cfg(feature = "parallel")]
+use super::PvError;
+use super::{PvSimulator, Record};
+#[cfg(feature = "parallel")]
+use rayon::{prelude::*, ThreadPoolBuilder};
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+/// The number of `Record`s reduced as one unit of work.
+/// Partial results are always combined in the same order, so serial and parallel
+/// reductions yield identical results independent of the number of threads.
+const CHUNK_SIZE: usize = 4096;
+
+impl PvSimulator {
+    /// Sets the number of worker threads used to analyse the `Record`s, e.g. for energy
+    /// summaries. The results are identical to a serial analysis.
+    /// Per default the `Record`s are analysed serially.
+    /// Fails if the number of threads is zero or the threads cannot be created.
+    ///
+    /// # Parameters
+    ///
+    /// * `threads` - the number of worker threads, if the analysis is parallelised
+    #[cfg(feature = "parallel")]
+    pub fn set_parallelism(&mut self, threads: Option<usize>) -> Result<(), PvError> {
+        self.thread_pool = match threads {
+            Some(0) => {
+                return Err(PvError::InternalError(
+                    "At least one worker thread is required.".to_string(),
+                ))
+            },
+            Some(threads) => Some(Arc::new(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|err| PvError::InternalError(err.to_string()))?,
+            )),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Returns the number of worker threads used to analyse the `Record`s.
+    /// Returns `None` if the `Record`s are analysed serially.
+    #[cfg(feature = "parallel")]
+    pub(super) fn worker_threads(&self) -> Option<usize> {
+        self.thread_pool
+            .as_ref()
+            .map(|thread_pool| thread_pool.current_num_threads())
+    }
+
+    /// Returns the number of worker threads used to analyse the `Record`s.
+    /// Returns `None` if the `Record`s are analysed serially.
+    #[cfg(not(feature = "parallel"))]
+    pub(super) fn worker_threads(&self) -> Option<usize> {
+        None
+    }
+
+    /// Reduces all `Record`s together with the time in hours each `Record` is representative
+    /// for. The `Record`s are folded in chunks, which are reduced in parallel if configured
+    /// (see `set_parallelism`), and the partial results are combined in chronological order.
+    ///
+    /// # Parameters
+    ///
+    /// * `init` - creates the initial value of each chunk
+    /// * `fold` - adds a `Record` and its interval in hours to the value of a chunk
+    /// * `combine` - combines the values of two subsequent chunks
+    pub(super) fn reduce_records<A, I, F, C>(&self, init: I, fold: F, combine: C) -> A
+    where
+        A: Send,
+        I: Fn() -> A + Sync,
+        F: Fn(A, &Record, f64) -> A + Sync,
+        C: Fn(A, A) -> A,
+    {
+        let records = &self.records[..];
+        let intervals_in_h = record_intervals_in_h(records);
+        let reduce_chunk = |(records, intervals): (&[Record], &[f64])| {
+            records
+                .iter()
+                .zip(intervals)
+                .fold(init(), |value, (record, interval_in_h)| {
+                    fold(value, record, *interval_in_h)
+                })
+        };
+        #[cfg(feature = "parallel")]
+        if let Some(thread_pool) = &self.thread_pool {
+            let partial_results: Vec<A> = thread_pool.install(|| {
+                records
+                    .par_chunks(CHUNK_SIZE)
+                    .zip(intervals_in_h.par_chunks(CHUNK_SIZE))
+                    .map(reduce_chunk)
+                    .collect()
+            });
+            return partial_results.into_iter().fold(init(), combine);
+        }
+        records
+            .chunks(CHUNK_SIZE)
+            .zip(intervals_in_h.chunks(CHUNK_SIZE))
+            .map(reduce_chunk)
+            .fold(init(), combine)
+    }
+}
@@ -0,0 +1,201 @@
+//! The `seasonal` module allows variation of the power output of the photovoltaic component
+//! over the year for sites without a known location.
+
+use super::super::pv_error::PvError;
+use super::{KumaraswamyModel, PvSimulator};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+/// The day of the year of the summer solstice on the northern hemisphere.
+const SUMMER_SOLSTICE_DAY: f64 = 172.0;
+/// The mean length of a year in days.
+const DAYS_PER_YEAR: f64 = 365.25;
+
+/// The `Seasonality` scales the power output and shortens the daylight hours towards
+/// the winter solstice.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Seasonality {
+    /// The fraction the peak output is reduced by at the winter solstice.
+    pub amplitude: f64,
+    /// The hours the daylight is shortened by at the winter solstice.
+    pub daylight_variation_in_h: f64,
+}
+
+impl Seasonality {
+    /// Returns the extent of winter at the specified date, which follows a cosine from zero
+    /// at the summer solstice to one at the winter solstice. The seasons of the southern
+    /// hemisphere are shifted by half a year.
+    ///
+    /// # Parameters
+    ///
+    /// * `date` - the simulated date
+    /// * `southern_hemisphere` - if the site is located on the southern hemisphere
+    fn winter_extent(&self, date: NaiveDate, southern_hemisphere: bool) -> f64 {
+        let mut phase = 2.0 * std::f64::consts::PI * (date.ordinal() as f64 - SUMMER_SOLSTICE_DAY)
+            / DAYS_PER_YEAR;
+        if southern_hemisphere {
+            phase += std::f64::consts::PI;
+        }
+        (1.0 - phase.cos()) / 2.0
+    }
+
+    /// Returns the factor the power output is scaled by at the specified date.
+    ///
+    /// # Parameters
+    ///
+    /// * `date` - the simulated date
+    /// * `southern_hemisphere` - if the site is located on the southern hemisphere
+    pub(super) fn scaling(&self, date: NaiveDate, southern_hemisphere: bool) -> f64 {
+        1.0 - self.amplitude * self.winter_extent(date, southern_hemisphere)
+    }
+
+    /// Returns dawn and dusk in hours from midnight at the specified date, which are shifted
+    /// symmetrically towards midday in winter. As the site has no known location, the seasons
+    /// of the northern hemisphere apply.
+    ///
+    /// # Parameters
+    ///
+    /// * `daylight_in_h` - dawn and dusk at the summer solstice
+    /// * `date` - the simulated date
+    pub(super) fn daylight_in_h(&self, daylight_in_h: (f64, f64), date: NaiveDate) -> (f64, f64) {
+        let shift = self.daylight_variation_in_h * self.winter_extent(date, false) / 2.0;
+        (daylight_in_h.0 + shift, daylight_in_h.1 - shift)
+    }
+}
+
+impl KumaraswamyModel {
+    /// Sets the `Seasonality` of the power output, so the peak output and daylight hours
+    /// vary with the date. Seasonal daylight hours only apply to sites without a location,
+    /// as their daylight hours already follow the date.
+    /// Fails if the amplitude is not within [0, 1] or if the daylight variation is negative
+    /// or not shorter than the daylight hours.
+    ///
+    /// # Parameters
+    ///
+    /// * `seasonality` - the seasonality of the power output, if any
+    pub fn set_seasonality(&mut self, seasonality: Option<Seasonality>) -> Result<(), PvError> {
+        if let Some(seasonality) = seasonality {
+            if !(0.0..=1.0).contains(&seasonality.amplitude) {
+                return Err(PvError::InternalError(format!(
+                    "{} is not a valid seasonal amplitude.",
+                    seasonality.amplitude
+                )));
+            }
+            if !(0.0..self.dusk_in_h - self.dawn_in_h)
+                .contains(&seasonality.daylight_variation_in_h)
+            {
+                return Err(PvError::InternalError(format!(
+                    "{} is not a valid variation of the daylight hours.",
+                    seasonality.daylight_variation_in_h
+                )));
+            }
+        }
+        self.seasonality = seasonality;
+        Ok(())
+    }
+
+    /// Returns the scaling of the power output at the specified time point including the
+    /// seasonal variation, if any. Sites with a negative latitude follow the seasons of the
+    /// southern hemisphere.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the simulated time point
+    pub(super) fn seasonal_peak_scaling(&self, time: DateTime<Utc>) -> f64 {
+        match self.seasonality {
+            Some(seasonality) => {
+                let southern_hemisphere = self.location.is_some_and(|(latitude, _)| latitude < 0.0);
+                self.peak_scaling * seasonality.scaling(time.date_naive(), southern_hemisphere)
+            },
+            None => self.peak_scaling,
+        }
+    }
+}
+
+impl PvSimulator {
+    /// Sets the `Seasonality` of the power output of the `KumaraswamyModel`
+    /// (see `KumaraswamyModel::set_seasonality`).
+    /// Per default there is no seasonality and every date is simulated alike.
+    /// Fails if the seasonality is invalid or if another model is set.
+    ///
+    /// # Parameters
+    ///
+    /// * `seasonality` - the seasonality of the power output, if any
+    pub fn set_seasonality(&mut self, seasonality: Option<Seasonality>) -> Result<(), PvError> {
+        self.kumaraswamy_mut()?.set_seasonality(seasonality)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::BrokerMessage;
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    /// Tests if the seasonality results in a higher midday output and longer daylight in
+    /// summer than in winter.
+    fn test_set_seasonality() {
+        let seasonality = |amplitude, daylight_variation_in_h| {
+            Some(Seasonality {
+                amplitude,
+                daylight_variation_in_h,
+            })
+        };
+        let mut simulator = PvSimulator::new("");
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        assert!(simulator.set_seasonality(seasonality(1.5, 0.0)).is_err());
+        assert!(simulator.set_seasonality(seasonality(0.5, -1.0)).is_err());
+        assert!(simulator.set_seasonality(seasonality(0.5, 16.0)).is_err());
+        let output_at = |simulator: &PvSimulator, month, hour| {
+            let time = Utc.with_ymd_and_hms(2021, month, 21, hour, 0, 0).unwrap();
+            simulator
+                .message_to_record(BrokerMessage::new(0.0, time).unwrap())
+                .unwrap()
+                .pv_power_output
+        };
+        // Without seasonality every date is simulated alike.
+        assert_eq!(output_at(&simulator, 6, 13), output_at(&simulator, 12, 13));
+        simulator.set_seasonality(seasonality(0.5, 6.0)).unwrap();
+        let summer = output_at(&simulator, 6, 13);
+        let winter = output_at(&simulator, 12, 13);
+        assert!(summer > winter && winter > 0.0);
+        // The daylight is shortened by three hours at either end in winter.
+        assert!(output_at(&simulator, 6, 7) > 0.0);
+        assert_eq!(output_at(&simulator, 12, 7), 0.0);
+    }
+
+    #[test]
+    /// Tests if the seasonality of sites on the southern hemisphere is shifted by half a year.
+    fn test_set_seasonality_southern_hemisphere() {
+        let mut simulator = PvSimulator::new("");
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        simulator
+            .set_seasonality(Some(Seasonality {
+                amplitude: 0.5,
+                daylight_variation_in_h: 0.0,
+            }))
+            .unwrap();
+        // Sydney at about local solar noon.
+        simulator.set_location(Some((-33.9, 151.2))).unwrap();
+        let peak_scaling = |simulator: &PvSimulator, month| {
+            simulator
+                .kumaraswamy()
+                .unwrap()
+                .seasonal_peak_scaling(Utc.with_ymd_and_hms(2021, month, 21, 2, 0, 0).unwrap())
+        };
+        assert!((peak_scaling(&simulator, 12) - 1650.0).abs() < 1.0);
+        assert!((peak_scaling(&simulator, 6) - 825.0).abs() < 1.0);
+        let output_at = |simulator: &PvSimulator, month| {
+            let time = Utc.with_ymd_and_hms(2021, month, 21, 2, 0, 0).unwrap();
+            simulator
+                .message_to_record(BrokerMessage::new(0.0, time).unwrap())
+                .unwrap()
+                .pv_power_output
+        };
+        // Long days coincide with the unreduced peak output.
+        assert!(output_at(&simulator, 12) > 2.0 * output_at(&simulator, 6));
+        // Sites on the northern hemisphere are unaffected.
+        simulator.set_location(Some((33.9, 151.2))).unwrap();
+        assert!((peak_scaling(&simulator, 6) - 1650.0).abs() < 1.0);
+    }
+}
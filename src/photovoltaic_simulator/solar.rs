@@ -0,0 +1,109 @@
+//! The `solar` module allows computation of the position of the sun.
+
+use chrono::{Datelike, NaiveDate, NaiveTime};
+
+/// The zenith angle of the sun at dawn and dusk in degrees, which accounts for atmospheric
+/// refraction and the size of the solar disc.
+const DAWN_DUSK_ZENITH_IN_DEG: f64 = 90.833;
+
+/// Computes dawn and dusk in local solar time at the specified latitude and date according to
+/// the NOAA solar position approximation. Local solar time corresponds to UTC at the prime
+/// meridian.
+/// During polar day dawn is at midnight and dusk right before the next midnight,
+/// while during polar night both are at noon.
+///
+/// # Parameters
+///
+/// * `latitude` - the latitude of the site in degrees, where northern latitudes are positive
+/// * `date` - the date to compute dawn and dusk for
+pub fn solar_dawn_dusk(latitude: f64, date: NaiveDate) -> (NaiveTime, NaiveTime) {
+    let days_in_year = if NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some() {
+        366.0
+    } else {
+        365.0
+    };
+    // The fractional year in radians at noon.
+    let gamma = 2.0 * std::f64::consts::PI / days_in_year * (date.ordinal0() as f64);
+    let equation_of_time_in_min = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = DAWN_DUSK_ZENITH_IN_DEG.to_radians().cos()
+        / (latitude.cos() * declination.cos())
+        - latitude.tan() * declination.tan();
+    if cos_hour_angle <= -1.0 {
+        // Polar day.
+        (
+            NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+        )
+    } else if cos_hour_angle >= 1.0 {
+        // Polar night.
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        (noon, noon)
+    } else {
+        // The sun moves by 4 minutes per degree of hour angle.
+        let hour_angle_in_min = 4.0 * cos_hour_angle.acos().to_degrees();
+        let solar_noon_in_min = 720.0 - equation_of_time_in_min;
+        (
+            minutes_to_time(solar_noon_in_min - hour_angle_in_min),
+            minutes_to_time(solar_noon_in_min + hour_angle_in_min),
+        )
+    }
+}
+
+/// Converts minutes since midnight to a `NaiveTime`, clamping the value to the same day.
+///
+/// # Parameters
+///
+/// * `minutes` - the minutes since midnight
+fn minutes_to_time(minutes: f64) -> NaiveTime {
+    let nanos = (minutes * 60_000_000_000.0).clamp(0.0, 86_399_999_999_999.0) as u64;
+    NaiveTime::from_num_seconds_from_midnight_opt(
+        (nanos / 1_000_000_000) as u32,
+        (nanos % 1_000_000_000) as u32,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the absolute difference between two times in minutes.
+    fn difference_in_min(a: NaiveTime, b: NaiveTime) -> f64 {
+        (a - b).num_seconds().abs() as f64 / 60.0
+    }
+
+    #[test]
+    /// Tests if the function `solar_dawn_dusk` matches reference values for Greenwich.
+    fn test_solar_dawn_dusk() {
+        // Sunrise and sunset at the Royal Observatory Greenwich on the summer solstice
+        // of 2020 were at 03:43 and 20:21 UTC.
+        let (dawn, dusk) = solar_dawn_dusk(51.48, NaiveDate::from_ymd_opt(2020, 6, 21).unwrap());
+        assert!(difference_in_min(dawn, NaiveTime::from_hms_opt(3, 43, 0).unwrap()) < 3.0);
+        assert!(difference_in_min(dusk, NaiveTime::from_hms_opt(20, 21, 0).unwrap()) < 3.0);
+        // On the winter solstice sunrise and sunset were at 08:04 and 15:53 UTC.
+        let (dawn, dusk) = solar_dawn_dusk(51.48, NaiveDate::from_ymd_opt(2020, 12, 21).unwrap());
+        assert!(difference_in_min(dawn, NaiveTime::from_hms_opt(8, 4, 0).unwrap()) < 3.0);
+        assert!(difference_in_min(dusk, NaiveTime::from_hms_opt(15, 53, 0).unwrap()) < 3.0);
+    }
+
+    #[test]
+    /// Tests if the function `solar_dawn_dusk` handles polar day and night.
+    fn test_solar_dawn_dusk_polar() {
+        let summer = NaiveDate::from_ymd_opt(2020, 6, 21).unwrap();
+        let (dawn, dusk) = solar_dawn_dusk(80.0, summer);
+        assert_eq!(dawn, NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        assert!(dusk > NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+        let (dawn, dusk) = solar_dawn_dusk(-80.0, summer);
+        assert_eq!(dawn, dusk);
+    }
+}
@@ -0,0 +1,146 @@
+//! The `stream` module allows writing `Record`s to a file as soon as they are observed,
+//! so long simulations do not accumulate all `Record`s in memory.
+
+use super::super::broker::{open_connection, with_connection};
+use super::super::message_source::MessageSource;
+use super::super::pv_error::PvError;
+use super::PvSimulator;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The number of streamed `Record`s after which the output file is flushed.
+const FLUSH_INTERVAL: usize = 1000;
+
+impl PvSimulator {
+    /// Listens for messages available from the broker like `listen_to_broker`, but writes
+    /// every `Record` to the specified file as newline delimited JSON as soon as it was
+    /// observed. See `stream_source_to_file` for details.
+    /// Fails if the messaging process fails or the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - the path to the output file
+    pub fn listen_and_stream_to_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PvError> {
+        let connection = open_connection(&self.broker_url, self.connection_retry)?;
+        with_connection(connection, |connection| {
+            self.consume_messages(connection, None, |simulator, source| {
+                simulator.stream_source_to_file(source, path)
+            })
+        })
+    }
+
+    /// Processes all messages of the source like `process_source`, but writes every `Record`
+    /// to the specified file as newline delimited JSON as soon as it was observed.
+    /// Previously observed `Record`s are discarded and only the last streamed `Record` is
+    /// retained, so the memory usage does not grow with the length of the simulation.
+    /// The file is flushed periodically and once the simulation ended.
+    /// Fails if a message cannot be obtained or processed or the file cannot be written.
+    ///
+    /// # Parameters
+    ///
+    /// * `source` - the source of the messages
+    /// * `path` - the path to the output file
+    pub fn stream_source_to_file<S: MessageSource, P: AsRef<Path>>(
+        &mut self,
+        source: &mut S,
+        path: P,
+    ) -> Result<(), PvError> {
+        if let Some(parent_directory) = path.as_ref().parent() {
+            create_dir_all(parent_directory)?;
+        }
+        let mut writer = BufWriter::new(File::create(path.as_ref())?);
+        self.records.clear();
+        // Retention is handled while streaming instead.
+        let max_retained = self.max_retained.take();
+        let mut streamed = 0;
+        let result = self
+            .process_source_with(source, |simulator, _| {
+                simulator.write_pending_records(&mut writer, &mut streamed)
+            })
+            // Write the last record if it was retained due to the deadband.
+            .and_then(|_| self.write_pending_records(&mut writer, &mut streamed));
+        self.max_retained = max_retained;
+        result?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes all `Record`s that were observed since the last call and discards all `Record`s
+    /// apart from the last one, which is required for comparison with the deadband.
+    ///
+    /// # Parameters
+    ///
+    /// * `writer` - the writer to write the `Record`s to
+    /// * `streamed` - the number of `Record`s written so far
+    fn write_pending_records<W: Write>(
+        &mut self,
+        writer: &mut W,
+        streamed: &mut usize,
+    ) -> Result<(), PvError> {
+        // The only retained record was already written, unless nothing was written so far.
+        let written = usize::from(*streamed > 0).min(self.records.len());
+        for record in &self.records[written..] {
+            serde_json::to_writer(&mut *writer, record)?;
+            writer.write_all(b"\n")?;
+            *streamed += 1;
+            if streamed.is_multiple_of(FLUSH_INTERVAL) {
+                writer.flush()?;
+            }
+        }
+        if self.records.len() > 1 {
+            self.records.drain(..self.records.len() - 1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::{BrokerMessage, Meter};
+    use super::super::super::simulated_time::SimulatedDateTime;
+    use super::super::Record;
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::VecDeque;
+    use std::fs::{read_to_string, remove_file};
+
+    #[test]
+    /// Tests if the function `stream_source_to_file` writes the same `Record`s as a buffered
+    /// simulation while retaining only the last `Record`.
+    fn test_stream_source_to_file() {
+        let meter = Meter::with_seed(9000.0, "", 42).unwrap();
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let mut messages: VecDeque<BrokerMessage> =
+            SimulatedDateTime::starting_at(start, Duration::minutes(1), Duration::days(1))
+                .map(|time_point| meter.sample_message(time_point).unwrap())
+                .collect();
+        messages.push_back(BrokerMessage::simulation_end_message());
+        let simulator = || {
+            let mut simulator = PvSimulator::with_seed("", 42);
+            // The deadband suppresses some records, including the last one at night.
+            simulator.set_deadband(Some(2000.0)).unwrap();
+            simulator
+        };
+        let mut buffered = simulator();
+        buffered.process_source(&mut messages.clone()).unwrap();
+        let path = std::env::temp_dir().join("pv_simulator_test_stream_source_to_file.jsonl");
+        let mut streaming = simulator();
+        streaming
+            .stream_source_to_file(&mut messages, &path)
+            .unwrap();
+        let content = read_to_string(&path).unwrap();
+        remove_file(path).expect("The test output file could not be removed.");
+        let streamed: Vec<Record> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(buffered.records().len() > 1 && buffered.records().len() < 1440);
+        assert_eq!(streamed.len(), buffered.records().len());
+        assert_eq!(streamed, buffered.records());
+        assert_eq!(
+            streaming.records(),
+            &buffered.records()[streamed.len() - 1..]
+        );
+    }
+}
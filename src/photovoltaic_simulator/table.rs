@@ -0,0 +1,266 @@
+//! The `table` module allows evaluation of the photovoltaic model by table lookup instead of
+//! computing the Kumaraswamy distribution for every message.
+
+use super::super::pv_error::PvError;
+use super::{clear_sky_output, KumaraswamyModel, PvSimulator};
+use chrono::{Duration, NaiveTime};
+
+/// The hours of a day.
+const HOURS_PER_DAY: f64 = 24.0;
+/// The smallest time between subsequent table entries in seconds, which limits the table to
+/// 86,401 entries.
+const MIN_RESOLUTION_IN_S: i64 = 1;
+
+/// An `OutputTable` containing the power output without noise for a peak scaling of 1
+/// at equidistant times of the day.
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct OutputTable {
+    resolution_in_h: f64,
+    daylight_in_h: (f64, f64),
+    values: Vec<f64>,
+}
+
+impl OutputTable {
+    /// Precomputes the power output at the specified resolution and daylight.
+    ///
+    /// # Parameters
+    ///
+    /// * `resolution_in_h` - the time between subsequent table entries in hours
+    /// * `daylight_in_h` - dawn and dusk in hours from midnight
+    fn new(resolution_in_h: f64, daylight_in_h: (f64, f64)) -> Self {
+        let steps = (HOURS_PER_DAY / resolution_in_h).ceil() as usize;
+        let values = (0..=steps)
+            .map(|step| {
+                let time_of_day_in_h = (step as f64 * resolution_in_h).min(HOURS_PER_DAY);
+                clear_sky_output(time_of_day_in_h, daylight_in_h, 1.0)
+            })
+            .collect();
+        OutputTable {
+            resolution_in_h,
+            daylight_in_h,
+            values,
+        }
+    }
+
+    /// Returns the power output for a peak scaling of 1 by linear interpolation between the
+    /// adjacent table entries.
+    /// The time of day is scaled from the specified daylight hours to the daylight hours of
+    /// the table, so the table also applies to days with other daylight hours.
+    ///
+    /// # Parameters
+    ///
+    /// * `time_of_day_in_h` - the time of day in hours from midnight
+    /// * `daylight_in_h` - dawn and dusk of the simulated day in hours from midnight
+    pub(super) fn lookup(&self, time_of_day_in_h: f64, daylight_in_h: (f64, f64)) -> f64 {
+        let (dawn, dusk) = daylight_in_h;
+        if time_of_day_in_h <= dawn || time_of_day_in_h >= dusk {
+            return 0.0;
+        }
+        let (table_dawn, table_dusk) = self.daylight_in_h;
+        let time_of_day_in_h =
+            table_dawn + (time_of_day_in_h - dawn) / (dusk - dawn) * (table_dusk - table_dawn);
+        let position = time_of_day_in_h.clamp(0.0, HOURS_PER_DAY) / self.resolution_in_h;
+        let last_index = self.values.len() - 1;
+        let lower = (position.floor() as usize).min(last_index);
+        let upper = (lower + 1).min(last_index);
+        let fraction = (position - lower as f64).clamp(0.0, 1.0);
+        self.values[lower] + (self.values[upper] - self.values[lower]) * fraction
+    }
+}
+
+impl KumaraswamyModel {
+    /// Precomputes the power output at the specified resolution, so the output is evaluated by
+    /// table lookup with linear interpolation instead of computing the Kumaraswamy distribution
+    /// at every time point.
+    /// The table is computed for the fixed dawn and dusk of the model and the time of day is
+    /// scaled to the seasonal daylight hours or the daylight hours at the location of the site.
+    /// Fails if the resolution is shorter than one second.
+    ///
+    /// # Parameters
+    ///
+    /// * `resolution` - the time between subsequent table entries
+    pub fn precompute_table(&mut self, resolution: Duration) -> Result<(), PvError> {
+        if resolution < Duration::seconds(MIN_RESOLUTION_IN_S) {
+            return Err(PvError::InternalError(format!(
+                "{} is shorter than the minimal table resolution of {} second.",
+                resolution, MIN_RESOLUTION_IN_S
+            )));
+        }
+        let resolution_in_h = resolution.num_nanoseconds().map_or(
+            resolution.num_milliseconds() as f64 / 3_600_000.0,
+            |nanos| nanos as f64 / 3_600_000_000_000.0,
+        );
+        self.output_table = Some(OutputTable::new(
+            resolution_in_h,
+            (self.dawn_in_h, self.dusk_in_h),
+        ));
+        Ok(())
+    }
+
+    /// Returns the precomputed power output in watt without seasonal variation for each
+    /// time of day, if a table was precomputed.
+    pub fn output_table(&self) -> Option<Vec<(NaiveTime, f64)>> {
+        let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        self.output_table.as_ref().map(|table| {
+            table
+                .values
+                .iter()
+                .enumerate()
+                .filter_map(|(step, value)| {
+                    let offset = Duration::nanoseconds(
+                        (step as f64 * table.resolution_in_h * 3_600_000_000_000.0) as i64,
+                    );
+                    // Entries at or after midnight of the next day are omitted.
+                    (offset < Duration::days(1))
+                        .then(|| (midnight + offset, value * self.peak_scaling))
+                })
+                .collect()
+        })
+    }
+}
+
+impl PvSimulator {
+    /// Precomputes the power output of the `KumaraswamyModel` at the specified resolution
+    /// (see `KumaraswamyModel::precompute_table`).
+    /// Random noise is still applied to the looked up output.
+    /// Fails if the resolution is shorter than one second or if another model is set.
+    ///
+    /// # Parameters
+    ///
+    /// * `resolution` - the time between subsequent table entries
+    pub fn precompute_table(&mut self, resolution: Duration) -> Result<(), PvError> {
+        self.kumaraswamy_mut()?.precompute_table(resolution)
+    }
+
+    /// Returns the precomputed power output in watt without noise for each time of day,
+    /// if a table was precomputed for the `KumaraswamyModel`.
+    pub fn output_table(&self) -> Option<Vec<(NaiveTime, f64)>> {
+        self.kumaraswamy().and_then(KumaraswamyModel::output_table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::meter::BrokerMessage;
+    use super::super::{
+        clear_sky_evaluations, normalised_time_of_day, RecordQuality, Seasonality,
+        DEFAULT_DAYLIGHT_IN_H,
+    };
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    /// Tests if the table based output matches the computed output.
+    fn test_precompute_table() {
+        let mut simulator = PvSimulator::new("");
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        assert_eq!(simulator.output_table(), None);
+        let computed = simulator.clone();
+        simulator.precompute_table(Duration::minutes(1)).unwrap();
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        for second in (0..86_400).step_by(7) {
+            let message = BrokerMessage::new(0.0, start + Duration::seconds(second)).unwrap();
+            let expected = computed.message_to_record(message).unwrap().pv_power_output;
+            let looked_up = simulator
+                .message_to_record(message)
+                .unwrap()
+                .pv_power_output;
+            assert!((expected - looked_up).abs() < 1.0);
+        }
+        let table = simulator.output_table().unwrap();
+        assert_eq!(table.len(), 24 * 60);
+        let (time, value) = table[14 * 60];
+        assert_eq!(time, NaiveTime::from_hms_opt(14, 0, 0).unwrap());
+        assert!(
+            (value
+                - clear_sky_output(
+                    normalised_time_of_day(time),
+                    DEFAULT_DAYLIGHT_IN_H,
+                    simulator.kumaraswamy().unwrap().peak_scaling
+                ))
+            .abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    /// Tests if the table lookup matches the computed output over many time points.
+    fn test_precompute_table_accuracy() {
+        let table = OutputTable::new(1.0 / 60.0, DEFAULT_DAYLIGHT_IN_H);
+        let times: Vec<f64> = (0..200_000).map(|i| (i % 86_400) as f64 / 3600.0).collect();
+        let computed: f64 = times
+            .iter()
+            .map(|time| clear_sky_output(*time, DEFAULT_DAYLIGHT_IN_H, 1.0))
+            .sum();
+        let looked_up: f64 = times
+            .iter()
+            .map(|time| table.lookup(*time, DEFAULT_DAYLIGHT_IN_H))
+            .sum();
+        assert!((computed - looked_up).abs() / computed < 1e-3);
+    }
+
+    #[test]
+    /// Tests if precomputation speeds up bulk evaluation, as the Kumaraswamy distribution is
+    /// only computed for the table entries instead of for every message.
+    fn test_precompute_table_speedup() {
+        let start = Utc.with_ymd_and_hms(2020, 6, 21, 0, 0, 0).unwrap();
+        let evaluate = |simulator: &PvSimulator| {
+            let evaluations = clear_sky_evaluations();
+            for second in 0..100_000 {
+                let message = BrokerMessage::new(0.0, start + Duration::seconds(second)).unwrap();
+                simulator.message_to_record(message).unwrap();
+            }
+            clear_sky_evaluations() - evaluations
+        };
+        let mut simulator = PvSimulator::new("");
+        assert_eq!(evaluate(&simulator), 100_000);
+        let evaluations = clear_sky_evaluations();
+        simulator.precompute_table(Duration::minutes(1)).unwrap();
+        assert!(clear_sky_evaluations() - evaluations <= 24 * 60 + 1);
+        assert_eq!(evaluate(&simulator), 0);
+    }
+
+    #[test]
+    /// Tests if the function `precompute_table` rejects resolutions shorter than one second.
+    fn test_precompute_table_resolution() {
+        let mut simulator = PvSimulator::new("");
+        assert!(simulator.precompute_table(Duration::zero()).is_err());
+        assert!(simulator
+            .precompute_table(Duration::nanoseconds(1))
+            .is_err());
+        assert!(simulator
+            .precompute_table(Duration::milliseconds(999))
+            .is_err());
+        assert_eq!(simulator.output_table(), None);
+        simulator.precompute_table(Duration::seconds(1)).unwrap();
+        assert_eq!(simulator.output_table().unwrap().len(), 86_400);
+    }
+
+    #[test]
+    /// Tests if the table based output matches the computed output for seasonal daylight
+    /// hours and at a location.
+    fn test_precompute_table_daylight() {
+        let mut simulator = PvSimulator::new("");
+        simulator.set_jitter_amplitude(0.0).unwrap();
+        simulator
+            .set_seasonality(Some(Seasonality {
+                amplitude: 0.5,
+                daylight_variation_in_h: 6.0,
+            }))
+            .unwrap();
+        let mut located = simulator.clone();
+        located.set_location(Some((48.14, 11.58))).unwrap();
+        for computed in [simulator, located] {
+            let mut table = computed.clone();
+            table.precompute_table(Duration::minutes(1)).unwrap();
+            let start = Utc.with_ymd_and_hms(2020, 12, 21, 0, 0, 0).unwrap();
+            for second in (0..86_400).step_by(7) {
+                let message = BrokerMessage::new(0.0, start + Duration::seconds(second)).unwrap();
+                let expected = computed.message_to_record(message).unwrap().pv_power_output;
+                let record = table.message_to_record(message).unwrap();
+                assert!((expected - record.pv_power_output).abs() < 1.0);
+                assert_eq!(record.quality, RecordQuality::Interpolated);
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! The `tariff` module allows estimation of the electricity costs of the `Record`s observed
+//! by a `PvSimulator`.
+
+use super::super::pv_error::PvError;
+use super::analysis::record_intervals_in_h;
+use super::PvSimulator;
+use chrono::NaiveTime;
+
+/// A `Tariff` specifies the prices of energy imported from and exported to the grid
+/// depending on the time of day.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tariff {
+    import_price: f64,
+    export_price: f64,
+    periods: Vec<TariffPeriod>,
+}
+
+/// A `TariffPeriod` overrides the default prices of a `Tariff` within a recurring
+/// daily time range.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct TariffPeriod {
+    start: NaiveTime,
+    end: NaiveTime,
+    import_price: f64,
+    export_price: f64,
+}
+
+impl Tariff {
+    /// Creates a new `Tariff` with constant prices throughout the day.
+    /// Fails, if any price is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `import_price` - the price per kWh imported from the grid
+    /// * `export_price` - the compensation per kWh exported to the grid
+    pub fn flat(import_price: f64, export_price: f64) -> Result<Self, PvError> {
+        validate_price(import_price)?;
+        validate_price(export_price)?;
+        Ok(Tariff {
+            import_price,
+            export_price,
+            periods: Vec::new(),
+        })
+    }
+
+    /// Adds a daily time range with different prices.
+    /// If the start lies after the end, the range wraps around midnight.
+    /// If periods overlap, the period added last takes precedence.
+    /// Fails, if the range is empty or if any price is not a positive finite number.
+    ///
+    /// # Parameters
+    ///
+    /// * `start` - the inclusive start of the time range
+    /// * `end` - the exclusive end of the time range
+    /// * `import_price` - the price per kWh imported from the grid
+    /// * `export_price` - the compensation per kWh exported to the grid
+    pub fn add_period(
+        &mut self,
+        start: NaiveTime,
+        end: NaiveTime,
+        import_price: f64,
+        export_price: f64,
+    ) -> Result<(), PvError> {
+        if start == end {
+            return Err(PvError::InternalError(format!(
+                "The tariff period from {} to {} is empty.",
+                start, end
+            )));
+        }
+        validate_price(import_price)?;
+        validate_price(export_price)?;
+        self.periods.push(TariffPeriod {
+            start,
+            end,
+            import_price,
+            export_price,
+        });
+        Ok(())
+    }
+
+    /// Returns the import and export prices per kWh at the specified time of day.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the time of day
+    fn prices_at(&self, time: NaiveTime) -> (f64, f64) {
+        self.periods
+            .iter()
+            .rev()
+            .find(|period| period.contains(time))
+            .map(|period| (period.import_price, period.export_price))
+            .unwrap_or((self.import_price, self.export_price))
+    }
+}
+
+impl TariffPeriod {
+    /// Checks if the specified time of day lies within the period.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the time of day
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// Checks if the specified price is a positive finite number.
+///
+/// # Parameters
+///
+/// * `price` - the price to check
+fn validate_price(price: f64) -> Result<(), PvError> {
+    if price.is_finite() && price.is_sign_positive() {
+        Ok(())
+    } else {
+        Err(PvError::InternalError(format!(
+            "{} is not a positive finite number.",
+            price
+        )))
+    }
+}
+
+impl PvSimulator {
+    /// Returns the electricity costs of all observed `Record`s according to the `Tariff`.
+    /// Energy imported from the grid is charged at the import price, while energy exported
+    /// to the grid is compensated at the export price, so negative costs indicate a net profit.
+    ///
+    /// # Parameters
+    ///
+    /// * `tariff` - the prices of imported and exported energy
+    pub fn compute_cost(&self, tariff: &Tariff) -> f64 {
+        self.records
+            .iter()
+            .zip(record_intervals_in_h(&self.records))
+            .map(|(record, interval_in_h)| {
+                let (import_price, export_price) = tariff.prices_at(record.time_stamp.time());
+                let net_energy_kwh = record.net_load() * interval_in_h / 1000.0;
+                if net_energy_kwh > 0.0 {
+                    net_energy_kwh * import_price
+                } else {
+                    net_energy_kwh * export_price
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::float_compare_non_exact;
+    use super::super::tests::simulator_with_records;
+    use super::*;
+
+    #[test]
+    /// Tests if the function `add_period` of the `Tariff` struct only accepts valid periods.
+    fn test_tariff_add_period() {
+        assert!(Tariff::flat(-0.3, 0.1).is_err());
+        assert!(Tariff::flat(0.3, f64::NAN).is_err());
+        let mut tariff = Tariff::flat(0.3, 0.1).unwrap();
+        let time = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        assert!(tariff.add_period(time, time, 0.2, 0.1).is_err());
+        assert!(tariff
+            .add_period(time, NaiveTime::from_hms_opt(9, 0, 0).unwrap(), -1.0, 0.1)
+            .is_err());
+        assert!(tariff.periods.is_empty());
+    }
+
+    #[test]
+    /// Tests if the function `compute_cost` equals the net energy times the rate
+    /// of a flat `Tariff`.
+    fn test_compute_cost_flat() {
+        // 600 Wh import and 500 Wh export.
+        let simulator = simulator_with_records(&[(500.0, 0.0), (200.0, 700.0), (100.0, 0.0)]);
+        let tariff = Tariff::flat(0.3, 0.3).unwrap();
+        assert!(float_compare_non_exact(
+            simulator.compute_cost(&tariff),
+            0.1 * 0.3
+        ));
+        let tariff = Tariff::flat(0.3, 0.1).unwrap();
+        assert!(float_compare_non_exact(
+            simulator.compute_cost(&tariff),
+            0.6 * 0.3 - 0.5 * 0.1
+        ));
+        assert_eq!(PvSimulator::new("").compute_cost(&tariff), 0.0);
+    }
+
+    #[test]
+    /// Tests if the function `compute_cost` applies the prices of the `Tariff` depending
+    /// on the time of day, including periods wrapping around midnight.
+    fn test_compute_cost_time_of_use() {
+        // Records at 00:00, 01:00 and 02:00, each importing 1 kWh.
+        let simulator = simulator_with_records(&[(1000.0, 0.0), (1000.0, 0.0), (1000.0, 0.0)]);
+        let mut tariff = Tariff::flat(0.3, 0.1).unwrap();
+        tariff
+            .add_period(
+                NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+                0.1,
+                0.1,
+            )
+            .unwrap();
+        tariff
+            .add_period(
+                NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+                0.2,
+                0.1,
+            )
+            .unwrap();
+        assert!(float_compare_non_exact(
+            simulator.compute_cost(&tariff),
+            0.1 + 0.3 + 0.2
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 //! The `pv_error` module lists all errors possible during execution of the simulation.
 
 /// `PvError` contains all runtime errors that might occur during simulation.
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum PvError {
     /// A general violation of contracts of internal componenets.
@@ -0,0 +1,71 @@
+//! The `seed_sequence` module allows reproducible seeding of all simulated components.
+extern crate rand;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A `SeedSequence` derives independent seeds for every simulated component from a single
+/// master seed, so each component is reproducible while their random values are uncorrelated.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SeedSequence {
+    master_seed: u64,
+}
+
+impl SeedSequence {
+    /// Creates a new `SeedSequence`.
+    ///
+    /// # Parameters
+    ///
+    /// * `master_seed` - the seed all component seeds are derived from
+    pub fn new(master_seed: u64) -> Self {
+        SeedSequence { master_seed }
+    }
+
+    /// Returns the seed for the `Meter`.
+    pub fn meter_seed(&self) -> u64 {
+        self.component_seed(0)
+    }
+
+    /// Returns the seed for the `PvSimulator`.
+    pub fn pv_simulator_seed(&self) -> u64 {
+        self.component_seed(1)
+    }
+
+    /// Derives the seed of a component from the master seed.
+    ///
+    /// # Parameters
+    ///
+    /// * `offset` - the offset identifying the component
+    fn component_seed(&self, offset: usize) -> u64 {
+        // Simply adding the offset to the master seed would share seeds between
+        // neighbouring master seeds, so the seeds are drawn from the master sequence instead.
+        let mut rng = StdRng::seed_from_u64(self.master_seed);
+        (0..offset).for_each(|_| {
+            rng.gen::<u64>();
+        });
+        rng.gen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Tests if the component seeds are reproducible and independent of each other.
+    fn test_component_seeds() {
+        let sequence = SeedSequence::new(42);
+        assert_eq!(sequence.meter_seed(), SeedSequence::new(42).meter_seed());
+        assert_eq!(
+            sequence.pv_simulator_seed(),
+            SeedSequence::new(42).pv_simulator_seed()
+        );
+        assert_ne!(sequence.meter_seed(), sequence.pv_simulator_seed());
+        assert_ne!(sequence.meter_seed(), SeedSequence::new(43).meter_seed());
+        // Offsets of neighbouring master seeds must not collide.
+        assert_ne!(
+            sequence.pv_simulator_seed(),
+            SeedSequence::new(43).meter_seed()
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! The `simulated_time` module allows simulation of time in fixed intervalls.
 extern crate chrono;
 
-use chrono::{DateTime, Duration, Utc};
+use super::pv_error::PvError;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
 
 /// `SimulatedDateTime` simulates a time point and its flow in fixed inervalls.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -10,10 +11,16 @@ pub struct SimulatedDateTime {
     current_time: DateTime<Utc>,
     stride: Duration,
     max_simulated_time: Duration,
+    inclusive_end: bool,
+    exhausted: bool,
 }
 
 impl SimulatedDateTime {
     /// Creates a new `SimulatedDateTime` that increases strictly monoton on every call.
+    /// The simulated time span is half-open, i.e. `[start, start + max_simulated_time)`,
+    /// so the time point at exactly the maximum length of the simulation is excluded and
+    /// a simulation of length `n * stride` yields exactly `n` time points.
+    /// Use `new_with_end` to include the final time point as in previous versions.
     ///
     /// # Parameters
     ///
@@ -24,29 +31,192 @@ impl SimulatedDateTime {
     ///
     /// If the `stride` is smaller or equal to zero.
     pub fn new(stride: Duration, max_simulated_time: Duration) -> Self {
-        if stride <= Duration::zero() {
-            panic!("The simulated time must increase strictly monoton!");
+        SimulatedDateTime::starting_at(Utc::now(), stride, max_simulated_time)
+    }
+
+    /// Creates a new `SimulatedDateTime` like `new`, but fails instead of panicking if the
+    /// `stride` is smaller or equal to zero or the end of the simulation is not representable.
+    ///
+    /// # Parameters
+    ///
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    pub fn try_new(stride: Duration, max_simulated_time: Duration) -> Result<Self, PvError> {
+        let starting_time = Utc::now();
+        validate(starting_time, stride, max_simulated_time)?;
+        Ok(SimulatedDateTime::starting_at(
+            starting_time,
+            stride,
+            max_simulated_time,
+        ))
+    }
+
+    /// Creates a new `SimulatedDateTime` like `new`, but allows to choose whether the time point
+    /// at exactly the maximum length of the simulation is simulated. If the end is inclusive,
+    /// a simulation of length `n * stride` yields `n + 1` time points, otherwise `n`.
+    /// Consequently, a simulation of zero length is only empty with an exclusive end.
+    ///
+    /// # Parameters
+    ///
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    /// * `inclusive_end` - `true` if the time point at the end of the simulation is simulated
+    ///
+    /// # Panics
+    ///
+    /// If the `stride` is smaller or equal to zero.
+    pub fn new_with_end(
+        stride: Duration,
+        max_simulated_time: Duration,
+        inclusive_end: bool,
+    ) -> Self {
+        SimulatedDateTime {
+            inclusive_end,
+            ..SimulatedDateTime::new(stride, max_simulated_time)
         }
+    }
+
+    /// Creates a new `SimulatedDateTime` like `new`, but starting at the current time
+    /// floored to whole seconds. Simulated time points are free of fractional seconds
+    /// as long as the `stride` is a whole number of seconds.
+    ///
+    /// # Parameters
+    ///
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    ///
+    /// # Panics
+    ///
+    /// If the `stride` is smaller or equal to zero.
+    pub fn new_aligned(stride: Duration, max_simulated_time: Duration) -> Self {
+        // Zero nanoseconds are always valid, so unwrapping cannot fail.
+        let starting_time = Utc::now().with_nanosecond(0).unwrap();
+        SimulatedDateTime::starting_at(starting_time, stride, max_simulated_time)
+    }
+
+    /// Creates a new `SimulatedDateTime` like `new`, but ensures that the `stride` divides the
+    /// maximum length of the simulation evenly, so there is no ragged final interval.
+    /// Fails if the `stride` is smaller or equal to zero, the end of the simulation is not
+    /// representable or the `stride` does not divide the maximum length of the simulation evenly.
+    ///
+    /// # Parameters
+    ///
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    pub fn new_strict(stride: Duration, max_simulated_time: Duration) -> Result<Self, PvError> {
         let starting_time = Utc::now();
+        validate(starting_time, stride, max_simulated_time)?;
+        let simulated_time =
+            SimulatedDateTime::starting_at(starting_time, stride, max_simulated_time);
+        if simulated_time.divides_evenly() {
+            Ok(simulated_time)
+        } else {
+            Err(PvError::InternalError(format!(
+                "The stride {} does not divide the simulation length {} evenly.",
+                stride, max_simulated_time
+            )))
+        }
+    }
+
+    /// Creates a new `SimulatedDateTime` continuing a previous simulation, so that the
+    /// first simulated time point follows the last time point of the previous simulation
+    /// after exactly one `stride`.
+    ///
+    /// # Parameters
+    ///
+    /// * `last` - the last time point of the previous simulation
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    ///
+    /// # Panics
+    ///
+    /// If the `stride` is smaller or equal to zero.
+    pub fn continue_from(
+        last: DateTime<Utc>,
+        stride: Duration,
+        max_simulated_time: Duration,
+    ) -> Self {
+        SimulatedDateTime::starting_at(last + stride, stride, max_simulated_time)
+    }
+
+    /// Creates a new `SimulatedDateTime` like `new`, but starting at the specified time point
+    /// instead of the current time, e.g. to simulate a specific historical day with
+    /// reproducible time stamps.
+    ///
+    /// # Parameters
+    ///
+    /// * `starting_time` - the first simulated time point
+    /// * `stride` - the `Duration` that is passing between two subsequent calls
+    /// * `max_simulated_time` - the maximum length of the simulation
+    ///
+    /// # Panics
+    ///
+    /// If the `stride` is smaller or equal to zero or the end of the simulation is not
+    /// representable.
+    pub fn starting_at(
+        starting_time: DateTime<Utc>,
+        stride: Duration,
+        max_simulated_time: Duration,
+    ) -> Self {
+        if let Err(PvError::InternalError(message)) =
+            validate(starting_time, stride, max_simulated_time)
+        {
+            panic!("{}", message);
+        }
         SimulatedDateTime {
             starting_time,
             current_time: starting_time,
             stride,
             max_simulated_time,
+            inclusive_end: false,
+            exhausted: false,
         }
     }
 
     /// Increments the `SimulatedDateTime` by its specified stride and returns the
     /// new simulated `DateTime` if the maximum simulation length is not exceeded.
+    /// The time point at exactly the maximum simulation length is only returned if the
+    /// end of the simulation is inclusive.
+    /// The simulation ends early if the next time point would not be representable.
     pub fn current_date_time(&mut self) -> Option<DateTime<Utc>> {
-        if self.current_time - self.starting_time > self.max_simulated_time {
+        let elapsed = self.current_time - self.starting_time;
+        let ended = if self.inclusive_end {
+            elapsed > self.max_simulated_time
+        } else {
+            elapsed >= self.max_simulated_time
+        };
+        if self.exhausted || ended {
             None
         } else {
             let old_time = self.current_time;
-            self.current_time = self.current_time + self.stride;
+            match self.current_time.checked_add_signed(self.stride) {
+                Some(next_time) => self.current_time = next_time,
+                None => self.exhausted = true,
+            }
             Some(old_time)
         }
     }
+
+    /// Checks if the maximum length of the simulation is an integer multiple of the `stride`.
+    /// Otherwise the last simulated time point precedes the end of the simulation by less
+    /// than a `stride`, which results in a ragged final interval.
+    pub fn divides_evenly(&self) -> bool {
+        match (
+            self.max_simulated_time.num_nanoseconds(),
+            self.stride.num_nanoseconds(),
+        ) {
+            (Some(length), Some(stride)) => length % stride == 0,
+            // Durations exceeding the nanosecond range are compared in milliseconds.
+            _ => self.max_simulated_time.num_milliseconds() % self.stride.num_milliseconds() == 0,
+        }
+    }
+
+    /// Returns the number of messages a `Meter` without schedule publishes for the remaining
+    /// simulation, which are all remaining time points plus the simulation-end-message.
+    pub fn expected_message_count(&self) -> usize {
+        // Counting a copy guarantees consistency with the simulated time points.
+        (*self).count() + 1
+    }
 }
 
 impl Iterator for SimulatedDateTime {
@@ -57,9 +227,104 @@ impl Iterator for SimulatedDateTime {
     }
 }
 
+/// Converts the specified number of seconds into a `Duration`.
+/// Fails if the number is not finite and positive.
+///
+/// # Parameters
+///
+/// * `seconds` - the number of seconds
+pub(crate) fn duration_from_secs(seconds: f64) -> Result<Duration, PvError> {
+    if seconds.is_finite() && seconds.is_sign_positive() {
+        Ok(Duration::nanoseconds((seconds * 1_000_000_000.0) as i64))
+    } else {
+        Err(PvError::InternalError(format!(
+            "{} is not a positive finite number.",
+            seconds
+        )))
+    }
+}
+
+/// Checks if the parameters specify a valid simulation.
+/// Fails if the `stride` is smaller or equal to zero or the end of the simulation is not
+/// representable.
+///
+/// # Parameters
+///
+/// * `starting_time` - the first simulated time point
+/// * `stride` - the `Duration` that is passing between two subsequent calls
+/// * `max_simulated_time` - the maximum length of the simulation
+fn validate(
+    starting_time: DateTime<Utc>,
+    stride: Duration,
+    max_simulated_time: Duration,
+) -> Result<(), PvError> {
+    if stride <= Duration::zero() {
+        Err(PvError::InternalError(
+            "The simulated time must increase strictly monoton!".to_string(),
+        ))
+    } else if starting_time
+        .checked_add_signed(max_simulated_time)
+        .is_none()
+    {
+        Err(PvError::InternalError(format!(
+            "A simulation of {} starting at {} exceeds the representable time range!",
+            max_simulated_time, starting_time
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// A `SimulationWindow` specifies a recurring time window on certain days of the week
+/// that should be simulated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SimulationWindow {
+    weekdays: Vec<Weekday>,
+    start_hour: u32,
+    end_hour: u32,
+}
+
+impl SimulationWindow {
+    /// Creates a new `SimulationWindow` spanning the hours from `start_hour` (inclusive)
+    /// to `end_hour` (exclusive) on each of the specified days of the week.
+    /// Fails if the hours do not specify a non-empty range within a day.
+    ///
+    /// # Parameters
+    ///
+    /// * `weekdays` - the days of the week the window recurs on
+    /// * `start_hour` - the inclusive first hour of the window
+    /// * `end_hour` - the exclusive last hour of the window
+    pub fn new(weekdays: Vec<Weekday>, start_hour: u32, end_hour: u32) -> Result<Self, PvError> {
+        if start_hour < end_hour && end_hour <= 24 {
+            Ok(SimulationWindow {
+                weekdays,
+                start_hour,
+                end_hour,
+            })
+        } else {
+            Err(PvError::InternalError(format!(
+                "{}-{} is not a valid range of hours.",
+                start_hour, end_hour
+            )))
+        }
+    }
+
+    /// Checks if the specified time point lies within this `SimulationWindow`.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - the time point to check
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.weekdays.contains(&time.weekday())
+            && time.hour() >= self.start_hour
+            && time.hour() < self.end_hour
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     /// Tests if the function `current_date_time` will correctly increase the simulated time
@@ -71,9 +336,8 @@ mod tests {
         // Simulate the initial time point.
         assert_eq!(Some(expected_date_time), simulated_time.current_date_time());
         // Simulate all further possible time points.
-        for _ in 0..5000 {
-            // AddAssign is not implemented for DateTime.
-            expected_date_time = expected_date_time + stride;
+        for _ in 1..5000 {
+            expected_date_time += stride;
             assert_eq!(Some(expected_date_time), simulated_time.current_date_time());
         }
         // The maximum simulation length was reached.
@@ -86,7 +350,7 @@ mod tests {
         let stride = Duration::seconds(5);
         let simulated_time = SimulatedDateTime::new(stride, Duration::seconds(1000));
         let start_date_time = simulated_time.starting_time;
-        let expected_dates: Vec<DateTime<Utc>> = (0..201)
+        let expected_dates: Vec<DateTime<Utc>> = (0..200)
             .map(|i| start_date_time + Duration::seconds(i * 5))
             .collect();
         // The maximum simulation length was reached.
@@ -96,6 +360,212 @@ mod tests {
         );
     }
 
+    #[test]
+    /// Tests if the simulated time span is half-open, so the end of the simulation is
+    /// never reached.
+    fn test_half_open() {
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let max_simulated_time = Duration::seconds(1000);
+        let time_stamps: Vec<DateTime<Utc>> =
+            SimulatedDateTime::starting_at(start, Duration::seconds(5), max_simulated_time)
+                .collect();
+        assert_eq!(time_stamps.len(), 200);
+        assert!(*time_stamps.last().unwrap() < start + max_simulated_time);
+    }
+
+    #[test]
+    /// Tests if the function `new_with_end` only includes the time point at the end of the
+    /// simulation if requested.
+    fn test_new_with_end() {
+        let stride = Duration::seconds(5);
+        let inclusive = SimulatedDateTime::new_with_end(stride, Duration::seconds(1000), true);
+        assert_eq!(inclusive.count(), 201);
+        assert_eq!(
+            inclusive.last().unwrap() - inclusive.starting_time,
+            Duration::seconds(1000)
+        );
+        let exclusive = SimulatedDateTime::new_with_end(stride, Duration::seconds(1000), false);
+        assert_eq!(exclusive.count(), 200);
+        assert_eq!(
+            exclusive.last().unwrap() - exclusive.starting_time,
+            Duration::seconds(995)
+        );
+        assert_eq!(exclusive.expected_message_count(), 201);
+        // Ragged final intervals are unaffected.
+        assert_eq!(
+            SimulatedDateTime::new_with_end(Duration::seconds(7), Duration::minutes(1), false)
+                .count(),
+            9
+        );
+        assert_eq!(
+            SimulatedDateTime::new_with_end(stride, Duration::zero(), false).count(),
+            0
+        );
+    }
+
+    #[test]
+    /// Tests if the function `expected_message_count` includes all remaining time points and
+    /// the simulation-end-message.
+    fn test_expected_message_count() {
+        let mut simulated_time =
+            SimulatedDateTime::new(Duration::seconds(5), Duration::seconds(1000));
+        assert_eq!(simulated_time.expected_message_count(), 201);
+        // Counting does not advance the simulated time.
+        assert_eq!(simulated_time.expected_message_count(), 201);
+        simulated_time.next();
+        assert_eq!(simulated_time.expected_message_count(), 200);
+        simulated_time.by_ref().for_each(drop);
+        assert_eq!(simulated_time.expected_message_count(), 1);
+    }
+
+    #[test]
+    /// Tests if the function `starting_at` simulates a fixed day with exact time stamps.
+    fn test_starting_at() {
+        let start = Utc.with_ymd_and_hms(2021, 6, 21, 0, 0, 0).unwrap();
+        let time_stamps: Vec<DateTime<Utc>> =
+            SimulatedDateTime::starting_at(start, Duration::hours(1), Duration::days(1)).collect();
+        assert_eq!(time_stamps.len(), 24);
+        assert_eq!(time_stamps[0], start);
+        assert_eq!(
+            *time_stamps.last().unwrap(),
+            Utc.with_ymd_and_hms(2021, 6, 21, 23, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests if the function `starting_at` will correctly panic on zero strides.
+    fn test_panic_starting_at_zero() {
+        SimulatedDateTime::starting_at(Utc::now(), Duration::zero(), Duration::seconds(1));
+    }
+
+    #[test]
+    /// Tests if the function `new_aligned` yields time points without fractional seconds.
+    fn test_new_aligned() {
+        let simulated_time =
+            SimulatedDateTime::new_aligned(Duration::seconds(5), Duration::hours(1));
+        assert_eq!(simulated_time.count(), 720);
+        for time in simulated_time {
+            assert_eq!(time.nanosecond(), 0);
+        }
+    }
+
+    #[test]
+    /// Tests if the function `divides_evenly` detects a ragged final interval.
+    fn test_divides_evenly() {
+        assert!(SimulatedDateTime::new(Duration::seconds(5), Duration::hours(1)).divides_evenly());
+        assert!(SimulatedDateTime::new(Duration::seconds(5), Duration::zero()).divides_evenly());
+        let ragged = SimulatedDateTime::new(Duration::seconds(7), Duration::minutes(1));
+        assert!(!ragged.divides_evenly());
+        // The last time point precedes the end of the simulation.
+        assert_eq!(
+            ragged.last().unwrap() - ragged.starting_time,
+            Duration::seconds(56)
+        );
+    }
+
+    #[test]
+    /// Tests if the function `new_strict` only accepts strides dividing the simulation
+    /// length evenly.
+    fn test_new_strict() {
+        assert!(SimulatedDateTime::new_strict(Duration::seconds(5), Duration::hours(1)).is_ok());
+        assert!(SimulatedDateTime::new_strict(Duration::seconds(7), Duration::minutes(1)).is_err());
+        assert!(SimulatedDateTime::new_strict(Duration::zero(), Duration::minutes(1)).is_err());
+    }
+
+    #[test]
+    /// Tests if the function `continue_from` produces a gapless continuation of a previous
+    /// simulation.
+    fn test_continue_from() {
+        let stride = Duration::seconds(5);
+        let first_chunk: Vec<DateTime<Utc>> =
+            SimulatedDateTime::new(stride, Duration::minutes(10)).collect();
+        let second_chunk: Vec<DateTime<Utc>> = SimulatedDateTime::continue_from(
+            *first_chunk.last().unwrap(),
+            stride,
+            Duration::minutes(10),
+        )
+        .collect();
+        assert_eq!(first_chunk.len(), second_chunk.len());
+        let chained: Vec<DateTime<Utc>> = first_chunk.into_iter().chain(second_chunk).collect();
+        for pair in chained.windows(2) {
+            assert_eq!(pair[1] - pair[0], stride);
+        }
+    }
+
+    #[test]
+    /// Tests if the simulation ends gracefully if the next time point is not representable.
+    fn test_current_date_time_overflow() {
+        let mut simulated_time = SimulatedDateTime::new(Duration::MAX, Duration::days(1));
+        assert_eq!(
+            simulated_time.current_date_time(),
+            Some(simulated_time.starting_time)
+        );
+        assert_eq!(simulated_time.current_date_time(), None);
+        assert_eq!(simulated_time.current_date_time(), None);
+        // The last time point is representable, but advancing beyond it is not.
+        let simulated_time = SimulatedDateTime {
+            inclusive_end: true,
+            ..SimulatedDateTime::starting_at(
+                DateTime::<Utc>::MAX_UTC - Duration::days(9),
+                Duration::days(3),
+                Duration::days(9),
+            )
+        };
+        assert_eq!(simulated_time.count(), 4);
+    }
+
+    #[test]
+    /// Tests if the function `try_new` rejects non-positive strides and unrepresentable
+    /// simulation lengths instead of panicking.
+    fn test_try_new() {
+        let simulated_time =
+            SimulatedDateTime::try_new(Duration::seconds(5), Duration::minutes(1)).unwrap();
+        assert_eq!(simulated_time.count(), 12);
+        assert!(SimulatedDateTime::try_new(Duration::zero(), Duration::minutes(1)).is_err());
+        assert!(SimulatedDateTime::try_new(Duration::seconds(-1), Duration::minutes(1)).is_err());
+        assert!(SimulatedDateTime::try_new(Duration::seconds(1), Duration::MAX).is_err());
+    }
+
+    #[test]
+    /// Tests if the function `duration_from_secs` only accepts positive finite numbers.
+    fn test_duration_from_secs() {
+        assert_eq!(
+            duration_from_secs(1.5).unwrap(),
+            Duration::milliseconds(1500)
+        );
+        assert!(duration_from_secs(-1.0).is_err());
+        assert!(duration_from_secs(f64::NAN).is_err());
+        assert!(duration_from_secs(f64::INFINITY).is_err());
+    }
+
+    #[test]
+    /// Tests if unrepresentable simulation lengths are rejected.
+    fn test_new_strict_overflow() {
+        assert!(SimulatedDateTime::new_strict(Duration::days(1), Duration::MAX).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests if the function `new` will correctly panic on unrepresentable simulation lengths.
+    fn test_panic_new_overflow() {
+        SimulatedDateTime::new(Duration::seconds(1), Duration::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests if the function `continue_from` will correctly panic on zero strides.
+    fn test_panic_continue_from_zero() {
+        SimulatedDateTime::continue_from(Utc::now(), Duration::zero(), Duration::seconds(1));
+    }
+
+    #[test]
+    #[should_panic]
+    /// Tests if the function `new_aligned` will correctly panic on zero strides.
+    fn test_panic_new_aligned_zero() {
+        SimulatedDateTime::new_aligned(Duration::zero(), Duration::seconds(1));
+    }
+
     #[test]
     #[should_panic]
     /// Tests if the function `new` will correctly panic on zero strides.
@@ -109,4 +579,30 @@ mod tests {
     fn test_panic_new_negative() {
         SimulatedDateTime::new(Duration::minutes(-12), Duration::seconds(1));
     }
+
+    #[test]
+    /// Tests if the function `new` of the `SimulationWindow` struct only creates valid
+    /// `SimulationWindow`s.
+    fn test_simulation_window_new() {
+        assert!(SimulationWindow::new(vec![Weekday::Mon], 0, 24).is_ok());
+        assert!(SimulationWindow::new(vec![Weekday::Mon], 8, 9).is_ok());
+        assert!(SimulationWindow::new(vec![Weekday::Mon], 9, 9).is_err());
+        assert!(SimulationWindow::new(vec![Weekday::Mon], 10, 9).is_err());
+        assert!(SimulationWindow::new(vec![Weekday::Mon], 0, 25).is_err());
+    }
+
+    #[test]
+    /// Tests if the function `contains` of the `SimulationWindow` struct matches the
+    /// correct days and hours.
+    fn test_simulation_window_contains() {
+        let window = SimulationWindow::new(vec![Weekday::Mon, Weekday::Tue], 6, 10).unwrap();
+        // 2020-06-22 is a monday.
+        let monday = Utc.with_ymd_and_hms(2020, 6, 22, 0, 0, 0).unwrap();
+        assert!(!window.contains(monday + Duration::minutes(359)));
+        assert!(window.contains(monday + Duration::hours(6)));
+        assert!(window.contains(monday + Duration::minutes(599)));
+        assert!(!window.contains(monday + Duration::hours(10)));
+        assert!(window.contains(monday + Duration::hours(24 + 8)));
+        assert!(!window.contains(monday + Duration::hours(48 + 8)));
+    }
 }